@@ -4,13 +4,24 @@ package barretenberg
 #cgo LDFLAGS: -lbarretenberg_ffi -lm -ldl -lpthread
 #include <stdlib.h>
 #include "libnoir_ffi/barretenberg_ffi.h"
+
+extern void goResultCallback(void *user_data, BBResult result);
+extern void goLogCallback(int32_t level, char *msg);
+extern void goProgressCallback(int32_t phase, float pct);
+extern void goWitnessCallback(void *user_data, uint32_t index, char *out_value);
+extern void goChunkCallback(void *user_data, uint8_t *data, size_t len);
 */
 import "C"
 import (
+	"encoding/base64"
+	"encoding/binary"
 	"encoding/json"
-	"errors"
 	"os"
+	"runtime/cgo"
 	"strings"
+	"sync"
+	"sync/atomic"
+	"time"
 	"unsafe"
 )
 
@@ -29,6 +40,8 @@ type ProofSystemSettings struct {
 	OracleHashType            OracleHashType `json:"oracle_hash_type"`            // Use HashPoseidon2, HashKeccak, or HashBlake2s
 	DisableZk                 bool           `json:"disable_zk"`                 // true for faster, non-private proofs
 	OptimizedSolidityVerifier bool           `json:"optimized_solidity_verifier"` // true for gas-optimized EVM verification
+	Threads                   uint32         `json:"threads,omitempty"`           // per-call thread count for the pipe backend, 0 keeps the backend's
+	CircuitName               string         `json:"circuit_name,omitempty"`      // name shown in backend diagnostics, "" means "circuit"
 }
 
 // DefaultSettings returns the default settings for UltraHonk (Poseidon2).
@@ -64,15 +77,162 @@ func GetBackendType() BackendType {
 	return BackendNative
 }
 
+// ConfigureBackend selects and initializes the backend explicitly.
+// bbBinaryPath is only used by the pipe backend; pass "" to use the default lookup.
+// For the pipe backend the binary is checked up front, see CheckBinary.
+// threads of 0 uses the number of available CPUs; the native backend takes its thread count from the
+// HARDWARE_CONCURRENCY environment variable instead.
+// Note: This must be called BEFORE any proving/verification functions, it fails if the backend is already initialized.
+func ConfigureBackend(t BackendType, bbBinaryPath string, threads uint32) error {
+	cType := C.CString(string(t))
+	defer C.free(unsafe.Pointer(cType))
+
+	var cPath *C.char
+	if bbBinaryPath != "" {
+		cPath = C.CString(bbBinaryPath)
+		defer C.free(unsafe.Pointer(cPath))
+	}
+
+	r := C.bb_configure_backend(cType, cPath, C.uint32_t(threads))
+	_, err := resultToBytes(r)
+	return err
+}
+
+// Shutdown stops the global backend, making sure the bb subprocess of the pipe backend exits, e.g. when
+// the host process shuts down. It waits for a call in progress to finish. Any later call starts a backend
+// again, lazily, with the type from SetBackendType rather than ConfigureBackend.
+func Shutdown() error {
+	r := C.bb_shutdown()
+	_, err := resultToBytes(r)
+	return err
+}
+
+// SetBinarySearchPaths sets the directories or files searched, in order, for the bb binary used by the
+// pipe backend when no explicit path is configured. A directory is searched for a file named bb. Passing
+// nil restores the defaults: PATH, ~/.aztec/bin and ~/.bb. BB_BINARY_PATH still takes priority.
+func SetBinarySearchPaths(paths []string) error {
+	pathsData, err := json.Marshal(paths)
+	if err != nil {
+		return err
+	}
+	cPaths := C.CString(string(pathsData))
+	defer C.free(unsafe.Pointer(cPaths))
+
+	r := C.bb_set_binary_search_paths(cPaths)
+	_, err = resultToBytes(r)
+	return err
+}
+
+// CheckBinary resolves the bb binary the pipe backend would use without an explicit path, checks that it
+// is executable and runs bb --version. It returns the path and version, or an error describing what is
+// missing. ConfigureBackend runs the same check for the pipe backend.
+func CheckBinary() (path string, version string, err error) {
+	r := C.bb_check_binary()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return "", "", err
+	}
+	var res struct {
+		Path    string `json:"path"`
+		Version string `json:"version"`
+	}
+	if err := json.Unmarshal(data, &res); err != nil {
+		return "", "", err
+	}
+	return res.Path, res.Version, nil
+}
+
+// ErrorCode classifies the errors reported by the backend.
+type ErrorCode int32
+
+const (
+	ErrCodeInvalidArgument ErrorCode = 1 // null pointer, malformed JSON or witness
+	ErrCodeDecode          ErrorCode = 2 // bytecode or proof buffer could not be decoded
+	ErrCodeBackend         ErrorCode = 3 // backend failed to initialize or returned an error
+	ErrCodeSerialization   ErrorCode = 4 // response could not be serialized
+	ErrCodeTimeout         ErrorCode = 5 // backend call exceeded the configured timeout
+	ErrCodePending         ErrorCode = 6 // polled job has not finished yet
+	ErrCodeCancelled       ErrorCode = 7 // operation was cancelled, or cannot be
+	ErrCodeUnsupported     ErrorCode = 8 // operation not supported by this build or backend
+	ErrCodeVersionMismatch ErrorCode = 9 // proof made by a different backend version than the verifier's
+)
+
+// Error is the error type returned by calls into the backend.
+type Error struct {
+	Code    ErrorCode
+	Message string
+	Context map[string]any // details such as "witness_index", only with SetErrorFormatJSON
+}
+
+func (e *Error) Error() string {
+	return e.Message
+}
+
+// ResetBackend tears down the backend and starts a fresh one with the same configuration.
+// Use it to recover after the bb subprocess of the pipe backend has died.
+func ResetBackend() error {
+	r := C.bb_reset_backend()
+	_, err := resultToBytes(r)
+	return err
+}
+
+// SetCallTimeout bounds how long a single pipe backend call may take; 0 disables the timeout.
+// The clock starts once the call has the backend to itself, so time queued behind other calls doesn't count.
+// A call that times out returns an ErrCodeTimeout error and its bb subprocess is restarted.
+// The native backend cannot be interrupted, so its calls are not timed.
+func SetCallTimeout(d time.Duration) {
+	C.bb_set_call_timeout_ms(C.uint32_t(d.Milliseconds()))
+}
+
+// SetSpawnRetry configures how the pipe backend retries spawning bb, e.g. on a transient resource
+// shortage: up to attempts tries in total, waiting delay before the first retry and doubling the wait
+// after each. The default is 3 attempts starting at 100ms. When all attempts fail the call returns an
+// ErrCodeBackend error.
+func SetSpawnRetry(attempts uint32, delay time.Duration) {
+	C.bb_set_spawn_retry(C.uint32_t(attempts), C.uint32_t(delay.Milliseconds()))
+}
+
+// SetThreadAffinity pins the pipe backend's bb process to as many CPUs as it has threads, for reproducible
+// benchmarks. It applies to backends started afterwards (see ResetBackend) and only on Linux; the native
+// backend isn't pinned. Fix the thread count with ConfigureBackend or BB_THREADS.
+func SetThreadAffinity(enabled bool) {
+	C.bb_set_thread_affinity(C.bool(enabled))
+}
+
+// ABIVersion returns the C ABI version of the linked library.
+func ABIVersion() uint32 {
+	return uint32(C.bb_abi_version())
+}
+
+// errorFormatJSON mirrors bb_set_error_format, so errors are parsed in the format they come in.
+var errorFormatJSON atomic.Bool
+
+// SetErrorFormatJSON makes the library report errors as JSON, which fills in Error.Context with details such
+// as the failing witness index or, for backend errors, the bb subprocess's output. Messages are the same.
+func SetErrorFormatJSON(enabled bool) {
+	errorFormatJSON.Store(enabled)
+	C.bb_set_error_format(C.bool(enabled))
+}
+
 // Result is a helper to convert C.BBResult to Go types
 func resultToBytes(r C.BBResult) ([]byte, error) {
 	if !bool(r.ok) {
 		if r.err == nil {
-			return nil, errors.New("unknown error from backend")
+			return nil, &Error{Code: ErrorCode(r.err_code), Message: "unknown error from backend"}
 		}
 		msg := C.GoString(r.err)
 		C.bb_free_err(r.err)
-		return nil, errors.New(msg)
+		e := &Error{Code: ErrorCode(r.err_code), Message: msg}
+		if errorFormatJSON.Load() {
+			var structured struct {
+				Message string         `json:"message"`
+				Context map[string]any `json:"context"`
+			}
+			if json.Unmarshal([]byte(msg), &structured) == nil {
+				e.Message, e.Context = structured.Message, structured.Context
+			}
+		}
+		return nil, e
 	}
 	defer C.bb_free_bytes(r.data)
 	if r.data.ptr == nil || r.data.len == 0 {
@@ -81,19 +241,79 @@ func resultToBytes(r C.BBResult) ([]byte, error) {
 	return C.GoBytes(unsafe.Pointer(r.data.ptr), C.int(r.data.len)), nil
 }
 
-// InitSRS initializes the SRS from the bytecode
-func InitSRS(bytecode string) error {
+// InitSRS loads the SRS needed to prove the given bytecode, so the first proof is warm.
+// It returns the number of SRS points loaded (the circuit's dyadic size).
+func InitSRS(bytecode string) (uint32, error) {
 	cBytecode := C.CString(bytecode)
 	defer C.free(unsafe.Pointer(cBytecode))
 
 	r := C.bb_init_srs_from_bytecode(cBytecode)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return 0, err
+	}
+	if len(data) != 4 {
+		return 0, &Error{Code: ErrCodeSerialization, Message: "unexpected SRS size length"}
+	}
+	return binary.LittleEndian.Uint32(data), nil
+}
+
+// EnsureSRS loads the SRS for circuits of up to maxGates gates into the backend, so proving a sequence of growing
+// circuits doesn't stall on each larger SRS. It returns the number of points loaded (maxGates rounded up to a
+// power of two) and fails if the SRS directory holds fewer; InitSRS with the largest circuit downloads them.
+// Circuits larger than that fail until the SRS is loaded again, so pass the largest one.
+func EnsureSRS(maxGates uint32) (uint32, error) {
+	r := C.bb_ensure_srs(C.uint32_t(maxGates))
+	data, err := resultToBytes(r)
+	if err != nil {
+		return 0, err
+	}
+	if len(data) != 4 {
+		return 0, &Error{Code: ErrCodeSerialization, Message: "unexpected SRS size length"}
+	}
+	return binary.LittleEndian.Uint32(data), nil
+}
+
+// SetSRSPath sets the directory the backend loads (and downloads) the SRS from. An empty path restores
+// BB_CRS_PATH, then the backend's default. It affects pipe backends started afterwards, so call it before
+// the first proof. The native backend reads CRS_PATH from the environment instead, which has to be set
+// before the process starts.
+func SetSRSPath(path string) error {
+	cPath := C.CString(path)
+	defer C.free(unsafe.Pointer(cPath))
+
+	r := C.bb_set_srs_path(cPath)
 	_, err := resultToBytes(r)
 	return err
 }
 
+// SRSStatus describes where the SRS comes from and how much of it is loaded.
+type SRSStatus struct {
+	Path   string `json:"path"`   // empty when the backend default applies
+	Source string `json:"source"` // "set", "env" or "default"
+	Loaded bool   `json:"loaded"`
+	Points uint32 `json:"points"` // largest size loaded with InitSRS by the current backend
+}
+
+// GetSRSStatus reports the SRS configuration without starting a backend.
+func GetSRSStatus() (*SRSStatus, error) {
+	r := C.bb_srs_status()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var status SRSStatus
+	if err := json.Unmarshal(data, &status); err != nil {
+		return nil, err
+	}
+	return &status, nil
+}
+
 // ProveUltraHonk generates an UltraHonk proof for the given bytecode, witness JSON, and settings.
 // bytecode: base64 encoded gzipped bytecode from Nargo
-// witnessJson: JSON string like `{"witness": ["0x...", "0x..."]}`
+// witnessJson: JSON string like `{"witness": ["0x...", "0x..."]}`, or with explicit witness indices
+// for a sparse witness map: `{"witness": {"3": "0x...", "7": "0x..."}}`. Small values may also be given
+// as JSON integers: `{"witness": [3, 9]}`
 // settings: ProofSystemSettings struct
 func ProveUltraHonk(bytecode string, witnessJson string, settings ProofSystemSettings) ([]byte, error) {
 	cBytecode := C.CString(bytecode)
@@ -113,11 +333,46 @@ func ProveUltraHonk(bytecode string, witnessJson string, settings ProofSystemSet
 	return resultToBytes(r)
 }
 
-// GetVkUltraHonk returns the verification key for the given bytecode and settings.
-func GetVkUltraHonk(bytecode string, settings ProofSystemSettings) ([]byte, error) {
+// ProveUltraHonkWithVkOut proves like ProveUltraHonk and also returns the verification key the proof was
+// made with, from the same computation, so no separate GetVkUltraHonk call is needed.
+func ProveUltraHonkWithVkOut(bytecode string, witnessJson string, settings ProofSystemSettings) (proof []byte, vk []byte, err error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_with_vk_out(cBytecode, cWJSON, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, nil, err
+	}
+	if len(data) < 4 {
+		return nil, nil, &Error{Code: ErrCodeSerialization, Message: "unexpected proof and VK buffer length"}
+	}
+	proofLen := binary.LittleEndian.Uint32(data)
+	if uint64(len(data)-4) < uint64(proofLen) {
+		return nil, nil, &Error{Code: ErrCodeSerialization, Message: "unexpected proof and VK buffer length"}
+	}
+	return data[4 : 4+proofLen], data[4+proofLen:], nil
+}
+
+// ProveUltraHonkGz is ProveUltraHonk with the witness JSON base64 encoded and gzipped, like the bytecode,
+// which keeps the payload small for large witnesses.
+func ProveUltraHonkGz(bytecode string, witnessJsonB64Gz string, settings ProofSystemSettings) ([]byte, error) {
 	cBytecode := C.CString(bytecode)
 	defer C.free(unsafe.Pointer(cBytecode))
 
+	cWitness := C.CString(witnessJsonB64Gz)
+	defer C.free(unsafe.Pointer(cWitness))
+
 	settingsData, err := json.Marshal(settings)
 	if err != nil {
 		return nil, err
@@ -125,28 +380,1866 @@ func GetVkUltraHonk(bytecode string, settings ProofSystemSettings) ([]byte, erro
 	cSettings := C.CString(string(settingsData))
 	defer C.free(unsafe.Pointer(cSettings))
 
-	r := C.bb_get_vk_ultrahonk(cBytecode, cSettings)
+	r := C.bb_prove_ultrahonk_gz(cBytecode, cWitness, cSettings)
 	return resultToBytes(r)
 }
 
-// VerifyUltraHonk verifies a proof using the verification key and settings.
-func VerifyUltraHonk(proof []byte, vk []byte, settings ProofSystemSettings) bool {
-	if len(proof) == 0 || len(vk) == 0 {
-		return false
+// ProveUltraHonkKeccak is ProveUltraHonk with settings.OracleHashType forced to HashKeccak, as needed by
+// the Solidity verifier. The other settings are used as given.
+func ProveUltraHonkKeccak(bytecode string, witnessJson string, settings ProofSystemSettings) ([]byte, error) {
+	return proveUltraHonkWithOracle(bytecode, witnessJson, settings, HashKeccak)
+}
+
+// ProveUltraHonkPoseidon is ProveUltraHonk with settings.OracleHashType forced to HashPoseidon2, for
+// proofs verified off-chain or recursively. The other settings are used as given.
+func ProveUltraHonkPoseidon(bytecode string, witnessJson string, settings ProofSystemSettings) ([]byte, error) {
+	return proveUltraHonkWithOracle(bytecode, witnessJson, settings, HashPoseidon2)
+}
+
+func proveUltraHonkWithOracle(bytecode string, witnessJson string, settings ProofSystemSettings, oracle OracleHashType) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	var r C.BBResult
+	if oracle == HashKeccak {
+		r = C.bb_prove_ultrahonk_keccak(cBytecode, cWJSON, cSettings)
+	} else {
+		r = C.bb_prove_ultrahonk_poseidon(cBytecode, cWJSON, cSettings)
+	}
+	return resultToBytes(r)
+}
+
+// ProveUltraHonkRawBytecode is ProveUltraHonk for bytecode that is already decompressed,
+// avoiding the base64 and gzip round trip for large circuits.
+func ProveUltraHonkRawBytecode(bytecode []byte, witnessJson string, settings ProofSystemSettings) ([]byte, error) {
+	if len(bytecode) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty bytecode"}
 	}
 
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
 	settingsData, err := json.Marshal(settings)
 	if err != nil {
-		return false
+		return nil, err
 	}
 	cSettings := C.CString(string(settingsData))
 	defer C.free(unsafe.Pointer(cSettings))
-	
-	return bool(C.bb_verify_ultrahonk(
-		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
-		C.uintptr_t(len(proof)),
-		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
-		C.uintptr_t(len(vk)),
+
+	r := C.bb_prove_ultrahonk_raw_bytecode((*C.uint8_t)(unsafe.Pointer(&bytecode[0])), C.uintptr_t(len(bytecode)), cWJSON, cSettings)
+	return resultToBytes(r)
+}
+
+// ProveUltraHonkWithVk is ProveUltraHonk using a verification key the caller already computed,
+// e.g. with GetVkUltraHonk, instead of computing it again. The key must belong to the same bytecode
+// and settings.
+func ProveUltraHonkWithVk(bytecode string, witnessJson string, vk []byte, settings ProofSystemSettings) ([]byte, error) {
+	if len(vk) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty verification key"}
+	}
+
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_with_vk(cBytecode, cWJSON, (*C.uint8_t)(unsafe.Pointer(&vk[0])), C.uintptr_t(len(vk)), cSettings)
+	return resultToBytes(r)
+}
+
+// WitnessStackItem is one entry of a multi-circuit witness stack.
+type WitnessStackItem struct {
+	CircuitIndex uint32   `json:"circuit_index"`
+	Witness      []string `json:"witness"`
+}
+
+// ProveUltraHonkStack generates an UltraHonk proof for a program whose witness stack has several entries.
+// The circuit indices must be 0, 1, 2, ... in order.
+func ProveUltraHonkStack(bytecode string, stack []WitnessStackItem, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	stackData, err := json.Marshal(stack)
+	if err != nil {
+		return nil, err
+	}
+	cStack := C.CString(string(stackData))
+	defer C.free(unsafe.Pointer(cStack))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_stack(cBytecode, cStack, cSettings)
+	return resultToBytes(r)
+}
+
+// ProveUltraHonkFromWitnessGz generates an UltraHonk proof from a witness in Noir's native format.
+// witnessGz is the content of the witness.gz file written by `nargo execute`.
+func ProveUltraHonkFromWitnessGz(bytecode string, witnessGz []byte, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWitness := C.CString(base64.StdEncoding.EncodeToString(witnessGz))
+	defer C.free(unsafe.Pointer(cWitness))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_from_witness_gz(cBytecode, cWitness, cSettings)
+	return resultToBytes(r)
+}
+
+// ProveUltraHonkWitnessBlob generates an UltraHonk proof from a witness stack already encoded as EncodeWitness
+// returns it, e.g. by an upstream service. The blob is passed to the backend without parsing its values; only
+// its leading format byte is checked.
+func ProveUltraHonkWitnessBlob(bytecode string, witnessBlob []byte, settings ProofSystemSettings) ([]byte, error) {
+	if len(witnessBlob) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty witness blob"}
+	}
+
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_witness_blob(
+		cBytecode,
+		(*C.uint8_t)(unsafe.Pointer(&witnessBlob[0])),
+		C.uintptr_t(len(witnessBlob)),
 		cSettings,
-	))
+	)
+	return resultToBytes(r)
+}
+
+// ProveUltraHonkFromFile is ProveUltraHonk reading its inputs from disk: bytecodePath is a nargo compile
+// artifact (.json) or a file of gzipped ACIR, and witnessPath a witness.gz from nargo execute (any .gz)
+// or a .json witness as taken by ProveUltraHonk.
+func ProveUltraHonkFromFile(bytecodePath string, witnessPath string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecodePath := C.CString(bytecodePath)
+	defer C.free(unsafe.Pointer(cBytecodePath))
+
+	cWitnessPath := C.CString(witnessPath)
+	defer C.free(unsafe.Pointer(cWitnessPath))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_from_file(cBytecodePath, cWitnessPath, cSettings)
+	return resultToBytes(r)
+}
+
+// RawProof is a proof split into its raw bytes and its public inputs.
+type RawProof struct {
+	Proof        []byte
+	PublicInputs [][]byte // 32-byte big-endian field elements
+}
+
+// ProveUltraHonkRaw generates an UltraHonk proof like ProveUltraHonk, but returns the raw proof
+// bytes and public inputs instead of the msgpack encoded response.
+func ProveUltraHonkRaw(bytecode string, witnessJson string, settings ProofSystemSettings) (*RawProof, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_raw(cBytecode, cWJSON, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+
+	if len(data) < 4 {
+		return nil, &Error{Code: ErrCodeDecode, Message: "raw proof too short"}
+	}
+	proofLen := binary.LittleEndian.Uint32(data)
+	data = data[4:]
+	if uint64(len(data)) < uint64(proofLen) {
+		return nil, &Error{Code: ErrCodeDecode, Message: "raw proof truncated"}
+	}
+	publicInputs, err := decodeFieldList(data[proofLen:])
+	if err != nil {
+		return nil, err
+	}
+	return &RawProof{Proof: data[:proofLen], PublicInputs: publicInputs}, nil
+}
+
+// ProveUltraHonkBatch proves the same circuit against several witnesses, computing the VK once.
+// witnessesJson is a JSON array of witness objects: `[{"witness": [...]}, {"witness": [...]}]`.
+// The result is a msgpack array with one {proof, error} map per witness, in input order;
+// a failing witness only sets its own error entry.
+func ProveUltraHonkBatch(bytecode string, witnessesJson string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessesJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_batch(cBytecode, cWJSON, cSettings)
+	return resultToBytes(r)
+}
+
+// ProveUltraHonkBundle proves like ProveUltraHonk, but returns a self-contained msgpack bundle
+// holding the proof, the VK it was proved with, the resolved settings and the backend version.
+// Store it as one artifact and check it later with VerifyBundle.
+func ProveUltraHonkBundle(bytecode string, witnessJson string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_bundle(cBytecode, cWJSON, cSettings)
+	return resultToBytes(r)
+}
+
+// VerifyBundle verifies a bundle from ProveUltraHonkBundle using the VK and settings it carries.
+// Errors are reported as by VerifyUltraHonkEx; a damaged bundle fails with ErrCodeDecode. A bundle
+// from another backend version that doesn't verify fails with ErrCodeVersionMismatch rather than
+// returning false.
+func VerifyBundle(bundle []byte) (bool, error) {
+	if len(bundle) == 0 {
+		return false, &Error{Code: ErrCodeInvalidArgument, Message: "empty bundle"}
+	}
+
+	r := C.bb_verify_bundle((*C.uint8_t)(unsafe.Pointer(&bundle[0])), C.uintptr_t(len(bundle)))
+	data, err := resultToBytes(r)
+	if err != nil {
+		return false, err
+	}
+	return len(data) == 1 && data[0] == 1, nil
+}
+
+// GetVkUltraHonk returns the verification key for the given bytecode and settings.
+func GetVkUltraHonk(bytecode string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_get_vk_ultrahonk(cBytecode, cSettings)
+	return resultToBytes(r)
+}
+
+// SetFieldRangeCheck enables or disables the check that witness values are below the field modulus.
+// It is enabled by default; disable it only to intentionally pass unreduced values.
+func SetFieldRangeCheck(enabled bool) {
+	C.bb_set_field_range_check(C.bool(enabled))
+}
+
+// SetFieldModulus sets the modulus, in hex, that witness values are range-checked against and negative values
+// are reduced by, for circuits over a field other than BN254's scalar field (the default). An empty string
+// restores BN254. It applies process-wide.
+func SetFieldModulus(modulusHex string) error {
+	cModulus := C.CString(modulusHex)
+	defer C.free(unsafe.Pointer(cModulus))
+
+	r := C.bb_set_field_modulus(cModulus)
+	_, err := resultToBytes(r)
+	return err
+}
+
+// SetFieldEndianness selects how 0x hex witness values are read: big-endian (the default) or, with
+// littleEndian, little-endian as emitted by some tools. Decimal values are unaffected, and so are the
+// public inputs of VerifyUltraHonkHex, which ProveUltraHonkHex always emits big-endian. It applies
+// process-wide.
+func SetFieldEndianness(littleEndian bool) {
+	C.bb_set_field_endianness(C.bool(littleEndian))
+}
+
+// ClearVkCache drops every verification key cached by previous prove/VK calls.
+func ClearVkCache() {
+	C.bb_clear_vk_cache()
+}
+
+// VerifyUltraHonk verifies a proof using the verification key and settings.
+func VerifyUltraHonk(proof []byte, vk []byte, settings ProofSystemSettings) bool {
+	if len(proof) == 0 || len(vk) == 0 {
+		return false
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return false
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+	
+	return bool(C.bb_verify_ultrahonk(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	))
+}
+
+// VerifyUltraHonkEx verifies a proof like VerifyUltraHonk, but distinguishes a
+// rejected proof (false, nil) from a failure to run verification (false, err).
+// A truncated or corrupt proof buffer fails with ErrCodeDecode.
+func VerifyUltraHonkEx(proof []byte, vk []byte, settings ProofSystemSettings) (bool, error) {
+	if len(proof) == 0 || len(vk) == 0 {
+		return false, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof or verification key"}
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return false, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_verify_ultrahonk_ex(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return false, err
+	}
+	return len(data) == 1 && data[0] == 1, nil
+}
+
+// VerifyUltraHonkStrict verifies a proof against the verification key of the given circuit, computed from
+// its bytecode (or taken from the VK cache), so a VK from another circuit can't be substituted. Results
+// and errors are reported as by VerifyUltraHonkEx.
+func VerifyUltraHonkStrict(bytecode string, proof []byte, settings ProofSystemSettings) (bool, error) {
+	if len(proof) == 0 {
+		return false, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof"}
+	}
+
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return false, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_verify_ultrahonk_strict(
+		cBytecode,
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return false, err
+	}
+	return len(data) == 1 && data[0] == 1, nil
+}
+
+// VerifyStatus is the outcome of verifying one proof in a batch.
+type VerifyStatus uint8
+
+const (
+	VerifyRejected VerifyStatus = 0 // the proof did not verify
+	VerifyOK       VerifyStatus = 1 // the proof verified
+	VerifyFailed   VerifyStatus = 2 // verification could not run, e.g. a corrupt proof; the reason is logged
+)
+
+// VerifyUltraHonkBatch verifies several proofs returned by ProveUltraHonk against the same
+// verification key, parsing the key and settings once. It returns one status per proof, in
+// input order; a bad proof only affects its own status.
+func VerifyUltraHonkBatch(proofs [][]byte, vk []byte, settings ProofSystemSettings) ([]VerifyStatus, error) {
+	if len(proofs) == 0 || len(vk) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "no proofs or empty verification key"}
+	}
+
+	// Each proof is already a msgpack map, so a msgpack array32 header followed by
+	// the proofs back to back is the array the backend expects.
+	size := 5
+	for _, p := range proofs {
+		size += len(p)
+	}
+	buf := make([]byte, 5, size)
+	buf[0] = 0xdd
+	binary.BigEndian.PutUint32(buf[1:], uint32(len(proofs)))
+	for _, p := range proofs {
+		buf = append(buf, p...)
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_verify_ultrahonk_batch(
+		(*C.uint8_t)(unsafe.Pointer(&buf[0])),
+		C.uintptr_t(len(buf)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	if len(data) != len(proofs) {
+		return nil, &Error{Code: ErrCodeSerialization, Message: "unexpected verification result count"}
+	}
+	statuses := make([]VerifyStatus, len(data))
+	for i, b := range data {
+		statuses[i] = VerifyStatus(b)
+	}
+	return statuses, nil
+}
+
+// ValidateProofBlob checks that proof is a well-formed proof as returned by ProveUltraHonk, without
+// verifying it. Truncated or corrupt buffers fail with ErrCodeDecode.
+func ValidateProofBlob(proof []byte) error {
+	if len(proof) == 0 {
+		return &Error{Code: ErrCodeDecode, Message: "empty proof buffer"}
+	}
+
+	r := C.bb_validate_proof_blob((*C.uint8_t)(unsafe.Pointer(&proof[0])), C.uintptr_t(len(proof)))
+	_, err := resultToBytes(r)
+	return err
+}
+
+// ProofPublicInputs extracts the public inputs from a proof returned by ProveUltraHonk,
+// without verifying it. Each element is a 32-byte big-endian field element.
+func ProofPublicInputs(proof []byte) ([][]byte, error) {
+	if len(proof) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof"}
+	}
+
+	r := C.bb_proof_public_inputs(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	return decodeFieldList(data)
+}
+
+// Compatibility tells whether a proof and a verification key fit together, and if not, why.
+type Compatibility struct {
+	Compatible bool     `json:"compatible"`
+	Reasons    []string `json:"reasons"`
+}
+
+// ProofVkCompatible checks, without running the verifier, whether a proof and a verification key have matching
+// shapes: the public input count, and a proof long enough for the key's circuit size. It is a cheap filter; a
+// compatible pair can still fail verification.
+func ProofVkCompatible(proof []byte, vk []byte) (*Compatibility, error) {
+	if len(proof) == 0 || len(vk) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof or verification key"}
+	}
+
+	r := C.bb_proof_vk_compatible(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var compat Compatibility
+	if err := json.Unmarshal(data, &compat); err != nil {
+		return nil, err
+	}
+	return &compat, nil
+}
+
+// VerifyUltraHonkRecover verifies the proof and returns the public inputs it verified with, as 32-byte field
+// elements. A proof that doesn't verify returns an error with code ErrCodeInvalidArgument.
+func VerifyUltraHonkRecover(proof []byte, vk []byte, settings ProofSystemSettings) ([][]byte, error) {
+	if len(proof) == 0 || len(vk) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof or verification key"}
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_verify_ultrahonk_recover(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	return decodeFieldList(data)
+}
+
+// decodeFieldList decodes a u32 LE count followed by that many 32-byte field elements.
+func decodeFieldList(data []byte) ([][]byte, error) {
+	if len(data) < 4 {
+		return nil, &Error{Code: ErrCodeDecode, Message: "field list too short"}
+	}
+	count := binary.LittleEndian.Uint32(data)
+	data = data[4:]
+	if uint64(len(data)) != uint64(count)*32 {
+		return nil, &Error{Code: ErrCodeDecode, Message: "field list length mismatch"}
+	}
+	fields := make([][]byte, count)
+	for i := range fields {
+		fields[i] = data[i*32 : (i+1)*32]
+	}
+	return fields, nil
+}
+
+// ProofFields is a proof split into its raw proof bytes and public inputs, hex encoded.
+type ProofFields struct {
+	Proof        string   `json:"proof"`
+	PublicInputs []string `json:"public_inputs"`
+}
+
+// ProofAsFields splits a proof returned by ProveUltraHonk into the raw proof and its public inputs,
+// as needed for on-chain verifier calldata.
+func ProofAsFields(proof []byte) (*ProofFields, error) {
+	if len(proof) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof"}
+	}
+
+	r := C.bb_proof_as_fields(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var fields ProofFields
+	if err := json.Unmarshal(data, &fields); err != nil {
+		return nil, err
+	}
+	return &fields, nil
+}
+
+// Verifier names an on-chain verifier for ProofForVerifier.
+type Verifier string
+
+const (
+	VerifierSolidityHonk  Verifier = "solidity_honk"
+	VerifierSolidityPlonk Verifier = "solidity_plonk" // not supported by the backend
+)
+
+// ProofForVerifier frames the proof as the given verifier's calldata, without the function selector. For
+// VerifierSolidityHonk that is the ABI-encoded (bytes proof, bytes32[] publicInputs) of the contract from
+// WriteSolidityVerifier; settings must use the Keccak oracle, as for HonkProofAsFields.
+func ProofForVerifier(proof []byte, verifier Verifier, settings ProofSystemSettings) ([]byte, error) {
+	if len(proof) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof"}
+	}
+
+	cVerifier := C.CString(string(verifier))
+	defer C.free(unsafe.Pointer(cVerifier))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_proof_for_verifier(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		cVerifier,
+		cSettings,
+	)
+	return resultToBytes(r)
+}
+
+// ProofToJSON converts a msgpack proof into JSON with the same field names and byte fields as 0x hex strings,
+// e.g. for storage or tools that only read JSON. ProofFromJSON converts it back.
+func ProofToJSON(proof []byte) ([]byte, error) {
+	if len(proof) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof"}
+	}
+
+	r := C.bb_proof_msgpack_to_json(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+	)
+	return resultToBytes(r)
+}
+
+// ProofFromJSON converts the JSON form of ProofToJSON back into a msgpack proof for VerifyUltraHonk.
+func ProofFromJSON(proofJson []byte) ([]byte, error) {
+	cProof := C.CString(string(proofJson))
+	defer C.free(unsafe.Pointer(cProof))
+
+	r := C.bb_proof_json_to_msgpack(cProof)
+	return resultToBytes(r)
+}
+
+// HonkProofFields is a proof laid out for the Solidity Honk verifier, as hex field elements.
+type HonkProofFields struct {
+	Proof        []string `json:"proof"`         // 16 pairing point fields, then the proof in transcript order
+	PublicInputs []string `json:"public_inputs"` // the circuit's own public inputs
+}
+
+// HonkProofAsFields lays out a proof from ProveUltraHonkKeccak as the Solidity verifier's
+// verify(bytes proof, bytes32[] publicInputs) expects it, the proof fields concatenated making up the bytes. settings must be the ones the proof was
+// made with: the Keccak oracle hash and no IPA accumulation.
+func HonkProofAsFields(proof []byte, settings ProofSystemSettings) (*HonkProofFields, error) {
+	if len(proof) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof"}
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_honk_proof_as_fields(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var fields HonkProofFields
+	if err := json.Unmarshal(data, &fields); err != nil {
+		return nil, err
+	}
+	return &fields, nil
+}
+
+// ProveUltraHonkHex is ProveUltraHonk returning the proof in ASCII-safe form: the raw proof as 0x-prefixed hex
+// and the public inputs as hex field elements. Verify it with VerifyUltraHonkHex.
+func ProveUltraHonkHex(bytecode string, witnessJson string, settings ProofSystemSettings) (*ProofFields, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_hex(cBytecode, cWJSON, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var fields ProofFields
+	if err := json.Unmarshal(data, &fields); err != nil {
+		return nil, err
+	}
+	return &fields, nil
+}
+
+// VerifyUltraHonkHex verifies a proof in the form returned by ProveUltraHonkHex or ProofAsFields.
+// Errors are reported as by VerifyUltraHonkEx.
+func VerifyUltraHonkHex(proof *ProofFields, vk []byte, settings ProofSystemSettings) (bool, error) {
+	if proof == nil || len(vk) == 0 {
+		return false, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof or verification key"}
+	}
+
+	proofData, err := json.Marshal(proof)
+	if err != nil {
+		return false, err
+	}
+	cProof := C.CString(string(proofData))
+	defer C.free(unsafe.Pointer(cProof))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return false, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_verify_ultrahonk_hex(
+		cProof,
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return false, err
+	}
+	return len(data) == 1 && data[0] == 1, nil
+}
+
+// WriteSolidityVerifier generates the Solidity verifier contract source for the given verification key.
+// Only settings with OracleHashType set to HashKeccak are supported.
+// The contract follows settings.DisableZk: false yields the ZK verifier, which only accepts ZK proofs,
+// and true yields the plain Honk verifier for non-ZK proofs. Use the settings the proofs were made with.
+func WriteSolidityVerifier(vk []byte, settings ProofSystemSettings) (string, error) {
+	if len(vk) == 0 {
+		return "", &Error{Code: ErrCodeInvalidArgument, Message: "empty verification key"}
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return "", err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_write_solidity_verifier(
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return "", err
+	}
+	return string(data), nil
+}
+
+// CircuitInfo describes the size of a circuit.
+type CircuitInfo struct {
+	NumGates        uint64 `json:"num_gates"`
+	SubgroupSize    uint64 `json:"subgroup_size"`    // gate count rounded up to a power of two
+	NumAcirOpcodes  uint64 `json:"num_acir_opcodes"`
+	NumPublicInputs uint64 `json:"num_public_inputs"`
+}
+
+// GetCircuitInfo returns the gate count, subgroup size and public input count of a circuit without proving it.
+func GetCircuitInfo(bytecode string, settings ProofSystemSettings) (*CircuitInfo, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_circuit_info(cBytecode, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var info CircuitInfo
+	if err := json.Unmarshal(data, &info); err != nil {
+		return nil, err
+	}
+	return &info, nil
+}
+
+// AcirFunctionStats counts the ACIR opcodes of one function of a program.
+type AcirFunctionStats struct {
+	Name          string            `json:"name"`
+	Opcodes       uint64            `json:"opcodes"`
+	Kinds         map[string]uint64 `json:"kinds"`           // per opcode kind, e.g. "AssertZero", "MemoryOp"
+	BlackBoxCalls map[string]uint64 `json:"black_box_calls"` // per black box function, e.g. "RANGE"
+}
+
+// AcirStats breaks a program's ACIR down by opcode kind, main function first.
+type AcirStats struct {
+	Functions []AcirFunctionStats `json:"functions"`
+}
+
+// GetAcirStats counts a circuit's ACIR opcodes by kind, reading only the bytecode. It is cheap, but gate counts
+// need GetCircuitInfo.
+func GetAcirStats(bytecode string) (*AcirStats, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	r := C.bb_acir_stats(cBytecode)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var stats AcirStats
+	if err := json.Unmarshal(data, &stats); err != nil {
+		return nil, err
+	}
+	return &stats, nil
+}
+
+// MemoryEstimate is the approximate peak memory of proving a circuit.
+type MemoryEstimate struct {
+	Bytes        uint64 `json:"bytes"`
+	SubgroupSize uint64 `json:"subgroup_size"`
+}
+
+// EstimateMemory returns a rough estimate of the memory needed to prove a circuit, linear in its subgroup
+// size, for scheduling proving jobs. It is a heuristic, not a bound.
+func EstimateMemory(bytecode string, settings ProofSystemSettings) (*MemoryEstimate, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_estimate_memory(cBytecode, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var estimate MemoryEstimate
+	if err := json.Unmarshal(data, &estimate); err != nil {
+		return nil, err
+	}
+	return &estimate, nil
+}
+
+// RecursiveProof holds everything an outer circuit needs to verify an inner proof.
+// All values are hex encoded field elements.
+type RecursiveProof struct {
+	Proof             []string `json:"proof"`
+	PublicInputs      []string `json:"public_inputs"`
+	AggregationObject []string `json:"aggregation_object"`
+	Vk                []string `json:"vk"`
+}
+
+// ProveUltraHonkRecursive generates a proof meant to be verified inside another Noir circuit.
+// The oracle hash is always Poseidon2, as required for recursive verification.
+func ProveUltraHonkRecursive(bytecode string, witnessJson string, settings ProofSystemSettings) (*RecursiveProof, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_ultrahonk_recursive(cBytecode, cWJSON, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var proof RecursiveProof
+	if err := json.Unmarshal(data, &proof); err != nil {
+		return nil, err
+	}
+	return &proof, nil
+}
+
+// VkAsFields returns the verification key as hex encoded field elements.
+func VkAsFields(vk []byte) ([]string, error) {
+	if len(vk) == 0 {
+		return nil, &Error{Code: ErrCodeInvalidArgument, Message: "empty verification key"}
+	}
+
+	r := C.bb_vk_as_fields((*C.uint8_t)(unsafe.Pointer(&vk[0])), C.uintptr_t(len(vk)))
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var fields []string
+	if err := json.Unmarshal(data, &fields); err != nil {
+		return nil, err
+	}
+	return fields, nil
+}
+
+// VkHash returns the 32-byte hash of the circuit's verification key, computed with the settings' oracle hash.
+// It is the vk_hash that `bb write_vk` writes, the value contracts register circuits by.
+func VkHash(bytecode string, settings ProofSystemSettings) ([]byte, error) {
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_vk_hash(cBytecode, cSettings)
+	return resultToBytes(r)
+}
+
+// VkFields returns the circuit's verification key as hex encoded field elements.
+func VkFields(bytecode string, settings ProofSystemSettings) ([]string, error) {
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_vk_fields(cBytecode, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var fields []string
+	if err := json.Unmarshal(data, &fields); err != nil {
+		return nil, err
+	}
+	return fields, nil
+}
+
+// BackendVersionInfo describes the backend in use.
+type BackendVersionInfo struct {
+	Backend    BackendType `json:"backend"`           // the backend actually running
+	Configured BackendType `json:"configured"`        // differs from Backend after a fallback from native
+	BbPath     string      `json:"bb_path,omitempty"` // pipe backend only
+	Version    string      `json:"version,omitempty"` // output of `bb --version`, pipe backend only
+	FfiVersion string      `json:"ffi_version"`
+}
+
+// BackendVersion reports which backend is in use and, for the pipe backend, the bb binary path and version.
+func BackendVersion() (*BackendVersionInfo, error) {
+	r := C.bb_backend_version()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var info BackendVersionInfo
+	if err := json.Unmarshal(data, &info); err != nil {
+		return nil, err
+	}
+	return &info, nil
+}
+
+// Capabilities lists what the linked library was built with.
+type Capabilities struct {
+	Backends         []BackendType `json:"backends"`
+	ProofSystems     []string      `json:"proof_systems"`
+	SolidityVerifier bool          `json:"solidity_verifier"`
+}
+
+// GetCapabilities reports the compiled-in backends and supported features, without initializing the backend.
+func GetCapabilities() (*Capabilities, error) {
+	r := C.bb_capabilities()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var caps Capabilities
+	if err := json.Unmarshal(data, &caps); err != nil {
+		return nil, err
+	}
+	return &caps, nil
+}
+
+// AsyncResult is the outcome of an asynchronous backend call.
+type AsyncResult struct {
+	Data []byte
+	Err  error
+}
+
+//export goResultCallback
+func goResultCallback(userData unsafe.Pointer, r C.BBResult) {
+	h := cgo.Handle(uintptr(userData))
+	ch := h.Value().(chan AsyncResult)
+	h.Delete()
+
+	data, err := resultToBytes(r)
+	ch <- AsyncResult{Data: data, Err: err}
+}
+
+// ProveUltraHonkAsync starts proving on the library's background threads and returns immediately.
+// The returned channel receives exactly one result, with the same contents as ProveUltraHonk.
+func ProveUltraHonkAsync(bytecode string, witnessJson string, settings ProofSystemSettings) <-chan AsyncResult {
+	ch := make(chan AsyncResult, 1)
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		ch <- AsyncResult{Err: err}
+		return ch
+	}
+
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	// The library copies the arguments, so they can be freed as soon as this returns.
+	h := cgo.NewHandle(ch)
+	C.bb_prove_ultrahonk_async(cBytecode, cWJSON, cSettings,
+		C.BBResultCallback(C.goResultCallback), unsafe.Pointer(uintptr(h)))
+	return ch
+}
+
+// ProveJob is a proof running in the background, started with BeginProveUltraHonk.
+type ProveJob uint64
+
+// BeginProveUltraHonk starts proving in the background and returns a handle to poll or cancel it.
+// Argument errors are reported by Poll.
+func BeginProveUltraHonk(bytecode string, witnessJson string, settings ProofSystemSettings) (ProveJob, error) {
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return 0, err
+	}
+
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	job := ProveJob(C.bb_prove_begin(cBytecode, cWJSON, cSettings))
+	if job == 0 {
+		return 0, &Error{Code: ErrCodeBackend, Message: "failed to start proving job"}
+	}
+	return job, nil
+}
+
+// Poll reports whether the job has finished. Once it has, it returns the same result as ProveUltraHonk
+// and the job is released; polling it again fails.
+func (j ProveJob) Poll() (done bool, proof []byte, err error) {
+	r := C.bb_prove_poll(C.uint64_t(j))
+	proof, err = resultToBytes(r)
+	if e, ok := err.(*Error); ok && e.Code == ErrCodePending {
+		return false, nil, nil
+	}
+	return true, proof, err
+}
+
+// Cancel aborts the job and releases it. With the pipe backend the running bb subprocess is killed
+// and restarted on the next call; the native backend cannot be interrupted mid-call, in which case
+// an ErrCodeCancelled error is returned and the job keeps running.
+func (j ProveJob) Cancel() error {
+	r := C.bb_prove_cancel(C.uint64_t(j))
+	_, err := resultToBytes(r)
+	return err
+}
+
+// JobStatus describes one job that has not been released yet.
+type JobStatus struct {
+	ID    ProveJob `json:"id"`
+	State string   `json:"state"` // "queued", "running" or "completed"
+	AgeMs uint64   `json:"age_ms"`
+}
+
+// JobsStatus counts the jobs by state and lists them ordered by id.
+type JobsStatus struct {
+	Queued    int         `json:"queued"`
+	Running   int         `json:"running"`
+	Completed int         `json:"completed"`
+	Jobs      []JobStatus `json:"jobs"`
+}
+
+// GetJobsStatus reports the jobs started with BeginProveUltraHonk that have not been polled to
+// completion or cancelled. It does not affect the jobs.
+func GetJobsStatus() (*JobsStatus, error) {
+	r := C.bb_jobs_status()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var status JobsStatus
+	if err := json.Unmarshal(data, &status); err != nil {
+		return nil, err
+	}
+	return &status, nil
+}
+
+// ProveTiming is how long the phases of a proof took, in milliseconds.
+type ProveTiming struct {
+	VkMs    uint64 `json:"vk_ms"` // 0 when the VK came from the cache
+	ProveMs uint64 `json:"prove_ms"`
+	TotalMs uint64 `json:"total_ms"` // includes decoding the inputs
+}
+
+// EnableTiming turns proving latency measurements on or off; they are off by default.
+func EnableTiming(enabled bool) {
+	C.bb_enable_timing(C.bool(enabled))
+}
+
+// LastTiming returns the timing of the last proof completed while timing was enabled, or nil if there is none.
+func LastTiming() (*ProveTiming, error) {
+	r := C.bb_last_timing()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var timing *ProveTiming
+	if err := json.Unmarshal(data, &timing); err != nil {
+		return nil, err
+	}
+	return timing, nil
+}
+
+// LogLevel is the severity of a log line from the backend.
+type LogLevel int32
+
+const (
+	LogError LogLevel = 0
+	LogWarn  LogLevel = 1
+	LogInfo  LogLevel = 2
+	LogDebug LogLevel = 3
+)
+
+var (
+	loggerMu sync.RWMutex
+	logger   func(level LogLevel, msg string)
+)
+
+//export goLogCallback
+func goLogCallback(level C.int32_t, msg *C.char) {
+	loggerMu.RLock()
+	f := logger
+	loggerMu.RUnlock()
+	if f != nil {
+		f(LogLevel(level), C.GoString(msg))
+	}
+}
+
+// SetLogger installs f to receive the library's log lines, including the stderr of bb subprocesses,
+// which arrives at LogDebug. f may be called from any goroutine. Pass nil to remove it.
+func SetLogger(f func(level LogLevel, msg string)) {
+	loggerMu.Lock()
+	logger = f
+	loggerMu.Unlock()
+
+	if f == nil {
+		C.bb_set_log_callback(nil)
+	} else {
+		C.bb_set_log_callback(C.BBLogCallback(C.goLogCallback))
+	}
+}
+
+// ProgressPhase is a phase of an UltraHonk proof reported to the progress callback.
+type ProgressPhase int32
+
+const (
+	PhaseComputingVk ProgressPhase = 0 // only on a VK cache miss
+	PhaseProving     ProgressPhase = 1
+	PhaseDone        ProgressPhase = 2
+)
+
+var (
+	progressMu sync.RWMutex
+	progress   func(phase ProgressPhase, pct float32)
+)
+
+//export goProgressCallback
+func goProgressCallback(phase C.int32_t, pct C.float) {
+	progressMu.RLock()
+	f := progress
+	progressMu.RUnlock()
+	if f != nil {
+		f(ProgressPhase(phase), float32(pct))
+	}
+}
+
+// SetProgressCallback installs f to receive the phases of UltraHonk proofs with a rough percentage done.
+// Progress is per phase only; there are no updates within one. f may be called from any goroutine. Pass
+// nil to remove it.
+func SetProgressCallback(f func(phase ProgressPhase, pct float32)) {
+	progressMu.Lock()
+	progress = f
+	progressMu.Unlock()
+
+	if f == nil {
+		C.bb_set_progress_callback(nil)
+	} else {
+		C.bb_set_progress_callback(C.BBProgressCallback(C.goProgressCallback))
+	}
+}
+
+// Metrics holds the library's counters since the process started.
+type Metrics struct {
+	ProofsStarted    uint64 `json:"proofs_started"`
+	ProofsSucceeded  uint64 `json:"proofs_succeeded"`
+	ProofsFailed     uint64 `json:"proofs_failed"`
+	VksComputed      uint64 `json:"vks_computed"` // cache hits excluded
+	VerificationsRun uint64 `json:"verifications_run"`
+	BackendCalls     uint64 `json:"backend_calls"`
+	BackendRestarts  uint64 `json:"backend_restarts"`
+}
+
+// GetMetrics snapshots the library's counters.
+func GetMetrics() (*Metrics, error) {
+	r := C.bb_metrics_json()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var m Metrics
+	if err := json.Unmarshal(data, &m); err != nil {
+		return nil, err
+	}
+	return &m, nil
+}
+
+// SetMaxBytecodeSize bounds the decompressed size of gzipped bytecode and witnesses, protecting
+// against gzip bombs. Larger inputs fail with ErrCodeDecode. 0 restores the default of 512 MiB.
+func SetMaxBytecodeSize(maxBytes uint64) {
+	C.bb_set_max_bytecode_size(C.uint64_t(maxBytes))
+}
+
+// SelfTestResult is how long each step of the self test took, in milliseconds.
+type SelfTestResult struct {
+	VkMs     uint64 `json:"vk_ms"`
+	ProveMs  uint64 `json:"prove_ms"`
+	VerifyMs uint64 `json:"verify_ms"`
+	TotalMs  uint64 `json:"total_ms"`
+}
+
+// SelfTest computes a VK for a tiny embedded circuit, proves it and verifies the proof.
+// It fails if the backend, the bb binary or the SRS is not usable, which makes it suitable as a readiness probe.
+func SelfTest() (*SelfTestResult, error) {
+	r := C.bb_self_test()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var res SelfTestResult
+	if err := json.Unmarshal(data, &res); err != nil {
+		return nil, err
+	}
+	return &res, nil
+}
+
+// WarmupResult is how long each step of Warmup took, in milliseconds.
+type WarmupResult struct {
+	SpawnMs     uint64 `json:"spawn_ms"`
+	RoundTripMs uint64 `json:"round_trip_ms"`
+	TotalMs     uint64 `json:"total_ms"`
+}
+
+// Warmup starts the backend, spawning bb for the pipe backend, and makes one cheap round trip to it, so
+// the first proof doesn't pay for the startup. It is cheaper than SelfTest as a readiness probe.
+func Warmup() (*WarmupResult, error) {
+	r := C.bb_warmup()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var res WarmupResult
+	if err := json.Unmarshal(data, &res); err != nil {
+		return nil, err
+	}
+	return &res, nil
+}
+
+// ProofSystemSupport says whether a proof system can be proven and verified through this library.
+type ProofSystemSupport struct {
+	Name   string `json:"name"` // "ultra_honk", "client_ivc", "mega_honk" or "plonk"
+	Prove  bool   `json:"prove"`
+	Verify bool   `json:"verify"`
+}
+
+// SupportedFlavors lists the valid proving configurations.
+type SupportedFlavors struct {
+	OracleHashes []OracleHashType      `json:"oracle_hashes"`
+	ProofSystems []ProofSystemSupport `json:"proof_systems"`
+	Zk           bool                 `json:"zk"`
+}
+
+// GetSupportedFlavors returns the oracle hashes and proof systems this library supports and whether ZK is
+// available, e.g. to offer only settings the backend accepts. It doesn't start a backend.
+func GetSupportedFlavors() (*SupportedFlavors, error) {
+	r := C.bb_supported_flavors()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var flavors SupportedFlavors
+	if err := json.Unmarshal(data, &flavors); err != nil {
+		return nil, err
+	}
+	return &flavors, nil
+}
+
+// GetVkMegaHonk computes the MegaHonk verification key of a circuit, as used in a ClientIVC stack.
+// bb cannot prove or verify a MegaHonk circuit on its own; use the ClientIVC flow for that.
+func GetVkMegaHonk(bytecode string) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	r := C.bb_get_vk_megahonk(cBytecode)
+	return resultToBytes(r)
+}
+
+// ProvePlonk mirrors ProveUltraHonk for UltraPlonk circuits. UltraPlonk was removed from bb, so it always
+// fails with ErrCodeUnsupported; Plonk proofs need an older bb.
+func ProvePlonk(bytecode string, witnessJson string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_plonk(cBytecode, cWJSON, cSettings)
+	return resultToBytes(r)
+}
+
+// GetVkPlonk is the counterpart of ProvePlonk; it always fails with ErrCodeUnsupported.
+func GetVkPlonk(bytecode string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_get_vk_plonk(cBytecode, cSettings)
+	return resultToBytes(r)
+}
+
+// VerifyPlonk is the counterpart of ProvePlonk; it always fails with ErrCodeUnsupported.
+func VerifyPlonk(proof []byte, vk []byte, settings ProofSystemSettings) (bool, error) {
+	if len(proof) == 0 || len(vk) == 0 {
+		return false, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof or verification key"}
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return false, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_verify_plonk(
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	if _, err := resultToBytes(r); err != nil {
+		return false, err
+	}
+	return true, nil
+}
+
+// ClientIVCStart opens a ClientIVC session that folds numCircuits circuit executions into a single proof.
+// Any session in progress is replaced. The session is lost if the backend is restarted.
+func ClientIVCStart(numCircuits uint32) error {
+	settingsData, err := json.Marshal(map[string]uint32{"num_circuits": numCircuits})
+	if err != nil {
+		return err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_civc_start(cSettings)
+	_, err = resultToBytes(r)
+	return err
+}
+
+// ClientIVCAccumulate folds one circuit execution into the session. The bytecode and witness use the same
+// formats as ProveUltraHonk.
+func ClientIVCAccumulate(bytecode string, witnessJson string) error {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	r := C.bb_civc_accumulate(cBytecode, cWJSON)
+	_, err := resultToBytes(r)
+	return err
+}
+
+// ClientIVCProve produces the proof once every circuit has been accumulated, and closes the session.
+// It returns the msgpack-encoded proof response.
+func ClientIVCProve() ([]byte, error) {
+	r := C.bb_civc_prove()
+	return resultToBytes(r)
+}
+
+// VerifyUltraHonkRaw verifies a proof from its components, as returned by ProveUltraHonkRaw,
+// without reassembling the msgpack response. Errors are reported as by VerifyUltraHonkEx.
+func VerifyUltraHonkRaw(proof *RawProof, vk []byte, settings ProofSystemSettings) (bool, error) {
+	if proof == nil || len(proof.Proof) == 0 || len(vk) == 0 {
+		return false, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof or verification key"}
+	}
+
+	publicInputs := make([]byte, 0, 32*len(proof.PublicInputs))
+	for _, input := range proof.PublicInputs {
+		if len(input) != 32 {
+			return false, &Error{Code: ErrCodeInvalidArgument, Message: "public input is not a 32-byte field element"}
+		}
+		publicInputs = append(publicInputs, input...)
+	}
+	var publicInputsPtr *C.uint8_t
+	if len(publicInputs) > 0 {
+		publicInputsPtr = (*C.uint8_t)(unsafe.Pointer(&publicInputs[0]))
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return false, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_verify_ultrahonk_parts_ex(
+		(*C.uint8_t)(unsafe.Pointer(&proof.Proof[0])),
+		C.uintptr_t(len(proof.Proof)),
+		publicInputsPtr,
+		C.uintptr_t(len(publicInputs)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return false, err
+	}
+	return len(data) == 1 && data[0] == 1, nil
+}
+
+// SaveVkCache writes the cached verification keys to path, for LoadVkCache in a later process.
+func SaveVkCache(path string) error {
+	cPath := C.CString(path)
+	defer C.free(unsafe.Pointer(cPath))
+
+	r := C.bb_vk_cache_save(cPath)
+	_, err := resultToBytes(r)
+	return err
+}
+
+// LoadVkCache adds the verification keys saved at path to the cache and returns how many were loaded.
+// A file written by a different backend or bb version is ignored and loads nothing.
+func LoadVkCache(path string) (int, error) {
+	cPath := C.CString(path)
+	defer C.free(unsafe.Pointer(cPath))
+
+	r := C.bb_vk_cache_load(cPath)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return 0, err
+	}
+	if len(data) != 4 {
+		return 0, &Error{Code: ErrCodeSerialization, Message: "unexpected VK count length"}
+	}
+	return int(binary.LittleEndian.Uint32(data)), nil
+}
+
+//export goWitnessCallback
+func goWitnessCallback(userData unsafe.Pointer, index C.uint32_t, out *C.char) {
+	witness := cgo.Handle(uintptr(userData)).Value().(func(uint32) string)
+	value := witness(uint32(index))
+
+	// Leave room for the terminating nul; an overlong value is cut and then rejected by the parser.
+	buf := unsafe.Slice((*byte)(unsafe.Pointer(out)), C.BB_WITNESS_VALUE_BUF_LEN)
+	copy(buf[:len(buf)-1], value)
+}
+
+// ProveUltraHonkWitnessFunc is ProveUltraHonk for very large witnesses: instead of a JSON document, the n witness
+// values are pulled from witness one at a time, in index order, each formatted as in witness JSON.
+func ProveUltraHonkWitnessFunc(bytecode string, n uint32, witness func(index uint32) string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	h := cgo.NewHandle(witness)
+	defer h.Delete()
+
+	r := C.bb_prove_ultrahonk_witness_stream(cBytecode, C.uint32_t(n),
+		C.BBWitnessCallback(C.goWitnessCallback), unsafe.Pointer(uintptr(h)), cSettings)
+	return resultToBytes(r)
+}
+
+//export goChunkCallback
+func goChunkCallback(userData unsafe.Pointer, data *C.uint8_t, n C.size_t) {
+	chunk := cgo.Handle(uintptr(userData)).Value().(func([]byte))
+	if n == 0 {
+		return
+	}
+	chunk(unsafe.Slice((*byte)(unsafe.Pointer(data)), int(n)))
+}
+
+// ProveUltraHonkChunked is ProveUltraHonk handing the proof to chunk in order, in pieces of at most
+// BB_PROOF_CHUNK_SIZE (64 KiB) bytes, instead of returning it in one buffer. The pieces are slices of the whole
+// proof, taken after it has been built. Each piece is only valid during the call, so copy what you keep. All pieces have been delivered when it returns without error; on error chunk
+// is never called.
+func ProveUltraHonkChunked(bytecode string, witnessJson string, settings ProofSystemSettings, chunk func([]byte)) error {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWitness := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWitness))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	h := cgo.NewHandle(chunk)
+	defer h.Delete()
+
+	r := C.bb_prove_ultrahonk_chunked(cBytecode, cWitness, cSettings,
+		C.BBChunkCallback(C.goChunkCallback), unsafe.Pointer(uintptr(h)))
+	_, err = resultToBytes(r)
+	return err
+}
+
+// ValidateSettings resolves settingsJson as the library would, without touching the backend. It returns the
+// complete settings that would be used, with missing fields defaulted, and a warning for every defaulted or
+// unknown key, so typos surface before proving. Invalid settings return an ErrCodeInvalidArgument error.
+func ValidateSettings(settingsJson string) (*ProofSystemSettings, []string, error) {
+	cSettings := C.CString(settingsJson)
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_validate_settings(cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, nil, err
+	}
+	var res struct {
+		Settings ProofSystemSettings `json:"settings"`
+		Warnings []string            `json:"warnings"`
+	}
+	if err := json.Unmarshal(data, &res); err != nil {
+		return nil, nil, err
+	}
+	return &res.Settings, res.Warnings, nil
+}
+
+// ProofSizes describes the size of a circuit's proofs and verification key.
+type ProofSizes struct {
+	// ProofBytes excludes the public inputs.
+	ProofBytes int `json:"proof_bytes"`
+	VkBytes    int `json:"vk_bytes"`
+	// PublicInputsCount counts the circuit's own public inputs, each 32 bytes.
+	PublicInputsCount uint64 `json:"public_inputs_count"`
+}
+
+// GetProofSizes returns the proof and verification key sizes for bytecode under settings, without a witness,
+// e.g. to check a circuit against calldata limits before proving. The first call for a circuit and settings
+// proves it once with an all-zero witness to measure the proof, so it takes as long as a prove.
+func GetProofSizes(bytecode string, settings ProofSystemSettings) (*ProofSizes, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_proof_sizes(cBytecode, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return nil, err
+	}
+	var sizes ProofSizes
+	if err := json.Unmarshal(data, &sizes); err != nil {
+		return nil, err
+	}
+	return &sizes, nil
+}
+
+// BackendHandle is an independent backend with its own bb subprocess (or native context). Calls on
+// different handles run in parallel, while the package-level functions share one global backend. A
+// handle's methods may be called concurrently, but not with or after Close.
+type BackendHandle struct {
+	h *C.BBHandle
+}
+
+// NewBackendHandle starts a backend of type t. bbBinaryPath and threads are as for ConfigureBackend:
+// "" uses the default lookup and 0 the number of available CPUs. Release it with Close.
+func NewBackendHandle(t BackendType, bbBinaryPath string, threads uint32) (*BackendHandle, error) {
+	configData, err := json.Marshal(struct {
+		BackendType string `json:"backend_type"`
+		BbPath      string `json:"bb_path,omitempty"`
+		Threads     uint32 `json:"threads,omitempty"`
+	}{string(t), bbBinaryPath, threads})
+	if err != nil {
+		return nil, err
+	}
+	cConfig := C.CString(string(configData))
+	defer C.free(unsafe.Pointer(cConfig))
+
+	h := C.bb_backend_new(cConfig)
+	if h == nil {
+		return nil, &Error{Code: ErrCodeBackend, Message: "failed to create backend (details go to the logger set with SetLogger)"}
+	}
+	return &BackendHandle{h: h}, nil
+}
+
+// Close shuts the backend down, once a call in progress on it returns. Calls made afterwards fail with
+// ErrCodeInvalidArgument.
+func (b *BackendHandle) Close() {
+	if b.h != nil {
+		C.bb_backend_free(b.h)
+		b.h = nil
+	}
+}
+
+// ProveUltraHonk is the package-level ProveUltraHonk run on this backend.
+func (b *BackendHandle) ProveUltraHonk(bytecode string, witnessJson string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_handle_prove_ultrahonk(b.h, cBytecode, cWJSON, cSettings)
+	return resultToBytes(r)
+}
+
+// VerifyUltraHonk is the package-level VerifyUltraHonkEx run on this backend.
+func (b *BackendHandle) VerifyUltraHonk(proof []byte, vk []byte, settings ProofSystemSettings) (bool, error) {
+	if len(proof) == 0 || len(vk) == 0 {
+		return false, &Error{Code: ErrCodeInvalidArgument, Message: "empty proof or verification key"}
+	}
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return false, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_handle_verify_ultrahonk(
+		b.h,
+		(*C.uint8_t)(unsafe.Pointer(&proof[0])),
+		C.uintptr_t(len(proof)),
+		(*C.uint8_t)(unsafe.Pointer(&vk[0])),
+		C.uintptr_t(len(vk)),
+		cSettings,
+	)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return false, err
+	}
+	return len(data) == 1 && data[0] == 1, nil
+}
+
+// GetVkUltraHonk is the package-level GetVkUltraHonk run on this backend.
+func (b *BackendHandle) GetVkUltraHonk(bytecode string, settings ProofSystemSettings) ([]byte, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return nil, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_handle_get_vk_ultrahonk(b.h, cBytecode, cSettings)
+	return resultToBytes(r)
+}
+
+// LastBackendOutput is the package-level LastBackendOutput for this backend, whose output no other caller
+// shares.
+func (b *BackendHandle) LastBackendOutput() (string, error) {
+	r := C.bb_handle_last_backend_output(b.h)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return "", err
+	}
+	return string(data), nil
+}
+
+// CompressProof gzips a proof and base64 encodes it, the same encoding as nargo's bytecode, for storage or
+// transport. DecompressProof reverses it.
+func CompressProof(proof []byte) (string, error) {
+	if len(proof) == 0 {
+		return "", &Error{Code: ErrCodeInvalidArgument, Message: "empty proof"}
+	}
+
+	r := C.bb_compress_proof((*C.uint8_t)(unsafe.Pointer(&proof[0])), C.uintptr_t(len(proof)))
+	data, err := resultToBytes(r)
+	if err != nil {
+		return "", err
+	}
+	return string(data), nil
+}
+
+// DecompressProof returns the proof compressed by CompressProof.
+func DecompressProof(compressed string) ([]byte, error) {
+	cCompressed := C.CString(compressed)
+	defer C.free(unsafe.Pointer(cCompressed))
+
+	r := C.bb_decompress_proof(cCompressed)
+	return resultToBytes(r)
+}
+
+// EncodeWitness returns the serialized witness that ProveUltraHonk sends to the backend for witnessJson,
+// so it can be prepared ahead of proving or on another machine, e.g. for a remote prover.
+func EncodeWitness(witnessJson string) ([]byte, error) {
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	r := C.bb_encode_witness(cWJSON)
+	return resultToBytes(r)
+}
+
+// LastBackendOutput returns what the bb subprocess of the global pipe backend wrote to stderr during its
+// last call, at most the last 64 KiB, e.g. to attach to the report of a failed proof. It waits for a call in
+// progress. The output is kept per backend, not per caller, so it is only meaningful while one caller uses
+// the backend: concurrent callers should each use a BackendHandle and its LastBackendOutput, or the
+// "backend_output" context of an error (see SetErrorFormatJSON), which is always that of the failed call.
+// It is empty for the native backend.
+func LastBackendOutput() (string, error) {
+	r := C.bb_last_backend_output()
+	data, err := resultToBytes(r)
+	if err != nil {
+		return "", err
+	}
+	return string(data), nil
+}
+
+// ValidateProveInputs checks the arguments of ProveUltraHonk without proving: the bytecode must decode,
+// every witness value parse and fit the field, and the settings be valid. It never calls the backend,
+// so it is cheap enough to reject bad requests before queueing them.
+func ValidateProveInputs(bytecode string, witnessJson string, settings ProofSystemSettings) error {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	cWJSON := C.CString(witnessJson)
+	defer C.free(unsafe.Pointer(cWJSON))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_prove_validate(cBytecode, cWJSON, cSettings)
+	_, err = resultToBytes(r)
+	return err
+}
+
+// PublicInputCount returns how many public inputs the circuit has, derived from its verification key
+// rather than from a proof, e.g. to size verifier calldata up front.
+func PublicInputCount(bytecode string, settings ProofSystemSettings) (uint32, error) {
+	cBytecode := C.CString(bytecode)
+	defer C.free(unsafe.Pointer(cBytecode))
+
+	settingsData, err := json.Marshal(settings)
+	if err != nil {
+		return 0, err
+	}
+	cSettings := C.CString(string(settingsData))
+	defer C.free(unsafe.Pointer(cSettings))
+
+	r := C.bb_public_input_count(cBytecode, cSettings)
+	data, err := resultToBytes(r)
+	if err != nil {
+		return 0, err
+	}
+	if len(data) != 4 {
+		return 0, &Error{Code: ErrCodeSerialization, Message: "unexpected public input count length"}
+	}
+	return binary.LittleEndian.Uint32(data), nil
 }
@@ -1,13 +1,23 @@
 package barretenberg
 
 import (
+	"bytes"
 	"encoding/base64"
+	"encoding/binary"
+	"encoding/hex"
 	"encoding/json"
 	"os"
+	"os/exec"
+	"path/filepath"
+	"regexp"
+	"strings"
+	"sync"
 	"testing"
+	"time"
 )
 
-func TestProveVerify(t *testing.T) {
+func loadTestCircuit(t *testing.T) (string, string) {
+	t.Helper()
 	// Read bytecode from testdata/circuit/target/circuit.json
 	data, err := os.ReadFile("testdata/circuit/target/circuit.json")
 	if err != nil {
@@ -31,17 +41,23 @@ func TestProveVerify(t *testing.T) {
 	}
 	witnessJSON, _ := json.Marshal(witness)
 
+	return circuit.Bytecode, string(witnessJSON)
+}
+
+func TestProveVerify(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
 	settings := DefaultSettings()
 
 	// 1. Prove
-	proof, err := ProveUltraHonk(circuit.Bytecode, string(witnessJSON), settings)
+	proof, err := ProveUltraHonk(bytecode, witnessJSON, settings)
 	if err != nil {
 		t.Fatalf("failed to prove: %v", err)
 	}
 	t.Logf("Proof length: %d", len(proof))
 
 	// 2. Get VK
-	vk, err := GetVkUltraHonk(circuit.Bytecode, settings)
+	vk, err := GetVkUltraHonk(bytecode, settings)
 	if err != nil {
 		t.Fatalf("failed to get VK: %v", err)
 	}
@@ -55,6 +71,852 @@ func TestProveVerify(t *testing.T) {
 	t.Logf("Verification success!")
 }
 
+func TestProveVerifyKeccak(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+	settings.OracleHashType = HashKeccak
+
+	proof, err := ProveUltraHonk(bytecode, witnessJSON, settings)
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+
+	verified, err := VerifyUltraHonkEx(proof, vk, settings)
+	if err != nil {
+		t.Fatalf("failed to verify: %v", err)
+	}
+	if !verified {
+		t.Fatalf("Keccak proof did not verify against Keccak VK")
+	}
+}
+
+func TestProveVerifyZkToggle(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	proofLen := map[bool]int{}
+	for _, disableZk := range []bool{false, true} {
+		settings := DefaultSettings()
+		settings.DisableZk = disableZk
+
+		proof, err := ProveUltraHonk(bytecode, witnessJSON, settings)
+		if err != nil {
+			t.Fatalf("disable_zk=%v: failed to prove: %v", disableZk, err)
+		}
+		vk, err := GetVkUltraHonk(bytecode, settings)
+		if err != nil {
+			t.Fatalf("disable_zk=%v: failed to get VK: %v", disableZk, err)
+		}
+		verified, err := VerifyUltraHonkEx(proof, vk, settings)
+		if err != nil {
+			t.Fatalf("disable_zk=%v: failed to verify: %v", disableZk, err)
+		}
+		if !verified {
+			t.Fatalf("disable_zk=%v: proof did not verify against its VK", disableZk)
+		}
+		proofLen[disableZk] = len(proof)
+	}
+
+	// The ZK proof carries the masking data on top of the plain proof.
+	if proofLen[false] <= proofLen[true] {
+		t.Fatalf("expected ZK proof (%d bytes) to be larger than non-ZK proof (%d bytes)", proofLen[false], proofLen[true])
+	}
+}
+
+func TestProveVerifyNegativeWitness(t *testing.T) {
+	bytecode, _ := loadTestCircuit(t)
+	settings := DefaultSettings()
+
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+
+	// x * x == y, with x given as a signed decimal.
+	cases := []struct {
+		name string
+		x, y string
+	}{
+		{"minus one", "-1", "1"},
+		{"minus zero", "-0", "0"},
+		// -(p - 3) == 3
+		{"large negative", "-21888242871839275222246405745257275088548364400416034343698204186575808495614", "9"},
+	}
+	for _, c := range cases {
+		t.Run(c.name, func(t *testing.T) {
+			witnessJSON, _ := json.Marshal(map[string][]string{"witness": {c.x, c.y}})
+			proof, err := ProveUltraHonk(bytecode, string(witnessJSON), settings)
+			if err != nil {
+				t.Fatalf("failed to prove: %v", err)
+			}
+			if !VerifyUltraHonk(proof, vk, settings) {
+				t.Fatalf("Verification failed")
+			}
+		})
+	}
+
+	witnessJSON, _ := json.Marshal(map[string][]string{"witness": {
+		"-21888242871839275222246405745257275088548364400416034343698204186575808495617", "0",
+	}})
+	if _, err := ProveUltraHonk(bytecode, string(witnessJSON), settings); err == nil {
+		t.Fatalf("expected error for magnitude equal to the field modulus")
+	}
+}
+
+func TestProveVerifyWitnessBlob(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+
+	blob, err := EncodeWitness(witnessJSON)
+	if err != nil {
+		t.Fatalf("failed to encode witness: %v", err)
+	}
+	proof, err := ProveUltraHonkWitnessBlob(bytecode, blob, settings)
+	if err != nil {
+		t.Fatalf("failed to prove from witness blob: %v", err)
+	}
+
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+	if !VerifyUltraHonk(proof, vk, settings) {
+		t.Fatalf("Verification failed")
+	}
+
+	blob[0] = 1
+	if _, err := ProveUltraHonkWitnessBlob(bytecode, blob, settings); err == nil {
+		t.Fatalf("expected an error for an unsupported format byte")
+	}
+}
+
+func TestProveWitnessCountMismatch(t *testing.T) {
+	bytecode, _ := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+
+	// The test circuit has two witnesses, x and y.
+	_, err := ProveUltraHonk(bytecode, `{"witness": ["3"]}`, settings)
+	if e, ok := err.(*Error); !ok || e.Code != ErrCodeInvalidArgument {
+		t.Fatalf("expected an invalid argument error for a short witness, got %v", err)
+	}
+
+	blob, err := EncodeWitness(`{"witness": ["3", "9", "0"]}`)
+	if err != nil {
+		t.Fatalf("failed to encode witness: %v", err)
+	}
+	_, err = ProveUltraHonkWitnessBlob(bytecode, blob, settings)
+	if e, ok := err.(*Error); !ok || e.Code != ErrCodeInvalidArgument {
+		t.Fatalf("expected an invalid argument error for a long witness blob, got %v", err)
+	}
+}
+
+func TestProveVerifyChunked(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+
+	var proof []byte
+	err := ProveUltraHonkChunked(bytecode, witnessJSON, settings, func(chunk []byte) {
+		proof = append(proof, chunk...)
+	})
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+	if !VerifyUltraHonk(proof, vk, settings) {
+		t.Fatalf("Verification of the reassembled proof failed")
+	}
+}
+
+// waitForJob polls job until it has finished.
+func waitForJob(t *testing.T, job ProveJob) ([]byte, error) {
+	t.Helper()
+	for {
+		done, proof, err := job.Poll()
+		if done {
+			return proof, err
+		}
+		time.Sleep(10 * time.Millisecond)
+	}
+}
+
+func TestProveJob(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+
+	job, err := BeginProveUltraHonk(bytecode, witnessJSON, settings)
+	if err != nil {
+		t.Fatalf("failed to start job: %v", err)
+	}
+	proof, err := waitForJob(t, job)
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+	if !VerifyUltraHonk(proof, vk, settings) {
+		t.Fatalf("Verification of the job's proof failed")
+	}
+
+	if _, _, err := job.Poll(); err == nil {
+		t.Fatalf("expected an error polling a released job")
+	}
+}
+
+func TestProveJobCancel(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	job, err := BeginProveUltraHonk(bytecode, witnessJSON, DefaultSettings())
+	if err != nil {
+		t.Fatalf("failed to start job: %v", err)
+	}
+	if err := job.Cancel(); err != nil {
+		// The native backend cannot interrupt a proof in progress.
+		if e, ok := err.(*Error); !ok || e.Code != ErrCodeCancelled {
+			t.Fatalf("expected ErrCodeCancelled, got %v", err)
+		}
+		if _, err := waitForJob(t, job); err != nil {
+			t.Fatalf("job failed after a refused cancel: %v", err)
+		}
+		return
+	}
+
+	if _, _, err := job.Poll(); err == nil {
+		t.Fatalf("expected an error polling a cancelled job")
+	}
+	// Whatever the cancel killed, the backend still serves later calls.
+	if _, err := ProveUltraHonk(bytecode, witnessJSON, DefaultSettings()); err != nil {
+		t.Fatalf("failed to prove after a cancel: %v", err)
+	}
+}
+
+func TestCallTimeout(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	realBb := os.Getenv("BB_BINARY_PATH")
+	if realBb == "" {
+		var err error
+		if realBb, err = exec.LookPath("bb"); err != nil {
+			t.Skipf("bb binary unavailable: %v", err)
+		}
+	}
+
+	// A bb that hangs the first time it is started, and is the real one after that.
+	dir := t.TempDir()
+	fakeBb := filepath.Join(dir, "bb")
+	script := "#!/bin/sh\nif mkdir '" + filepath.Join(dir, "hung") + "' 2>/dev/null; then exec sleep 600; fi\nexec '" + realBb + "' \"$@\"\n"
+	if err := os.WriteFile(fakeBb, []byte(script), 0o755); err != nil {
+		t.Fatalf("failed to write fake bb: %v", err)
+	}
+
+	b, err := NewBackendHandle(BackendPipe, fakeBb, 0)
+	if err != nil {
+		t.Fatalf("failed to start the pipe backend: %v", err)
+	}
+	defer b.Close()
+
+	SetCallTimeout(500 * time.Millisecond)
+	defer SetCallTimeout(0)
+	_, err = b.ProveUltraHonk(bytecode, witnessJSON, DefaultSettings())
+	if e, ok := err.(*Error); !ok || e.Code != ErrCodeTimeout {
+		t.Fatalf("expected ErrCodeTimeout, got %v", err)
+	}
+
+	// The killed bb subprocess is respawned, as the real bb, for the next call.
+	SetCallTimeout(0)
+	if _, err := b.ProveUltraHonk(bytecode, witnessJSON, DefaultSettings()); err != nil {
+		t.Fatalf("failed to prove after a timeout: %v", err)
+	}
+}
+
+func TestProveVerifyBundle(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	bundle, err := ProveUltraHonkBundle(bytecode, witnessJSON, DefaultSettings())
+	if err != nil {
+		t.Fatalf("failed to prove bundle: %v", err)
+	}
+
+	verified, err := VerifyBundle(bundle)
+	if err != nil {
+		t.Fatalf("failed to verify bundle: %v", err)
+	}
+	if !verified {
+		t.Fatalf("bundle did not verify")
+	}
+
+	if _, err := VerifyBundle(bundle[:len(bundle)/2]); err == nil {
+		t.Fatalf("expected an error for a truncated bundle")
+	}
+}
+
+func TestVerifyBundleVersionMismatch(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	bundle, err := ProveUltraHonkBundle(bytecode, witnessJSON, DefaultSettings())
+	if err != nil {
+		t.Fatalf("failed to prove bundle: %v", err)
+	}
+
+	// Pretend another producer: change the first character of the bb and library versions the bundle
+	// records, each a msgpack fixstr following its key.
+	otherVersion := func(key string) {
+		i := bytes.Index(bundle, append([]byte{byte(0xa0 | len(key))}, key...))
+		if i < 0 {
+			t.Fatalf("bundle has no %q", key)
+		}
+		v := i + 1 + len(key)
+		if bundle[v]&0xe0 != 0xa0 || bundle[v]&0x1f == 0 {
+			return // not recorded, e.g. the native backend's bb version
+		}
+		if bundle[v+1] == '9' {
+			bundle[v+1] = '8'
+		} else {
+			bundle[v+1] = '9'
+		}
+	}
+	otherVersion("version")
+	otherVersion("ffi_version")
+
+	// A proof that still verifies is accepted with a warning.
+	verified, err := VerifyBundle(bundle)
+	if err != nil || !verified {
+		t.Fatalf("expected a bundle from another version to verify, got %v, %v", verified, err)
+	}
+
+	// One that doesn't is reported as a version mismatch: change the public input y = 9 to 8.
+	i := bytes.Index(bundle, []byte("public_inputs"))
+	j := bytes.Index(bundle[i:], append(make([]byte, 31), 9))
+	if i < 0 || j < 0 {
+		t.Fatalf("bundle has no public input 9")
+	}
+	bundle[i+j+31] = 8
+	_, err = VerifyBundle(bundle)
+	if e, ok := err.(*Error); !ok || e.Code != ErrCodeVersionMismatch {
+		t.Fatalf("expected ErrCodeVersionMismatch, got %v", err)
+	}
+}
+
+func TestProofSizes(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	// Settings no other test proves with, so the size is measured rather than recorded.
+	settings := DefaultSettings()
+	settings.OracleHashType = HashKeccak
+	settings.DisableZk = true
+
+	sizes, err := GetProofSizes(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get proof sizes: %v", err)
+	}
+
+	proof, err := ProveUltraHonkRaw(bytecode, witnessJSON, settings)
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+	if sizes.ProofBytes != len(proof.Proof) {
+		t.Fatalf("proof size %d, proof is %d bytes", sizes.ProofBytes, len(proof.Proof))
+	}
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+	if sizes.VkBytes != len(vk) {
+		t.Fatalf("VK size %d, VK is %d bytes", sizes.VkBytes, len(vk))
+	}
+}
+
+func TestPlonkUnsupported(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+
+	_, err := ProvePlonk(bytecode, witnessJSON, settings)
+	if e, ok := err.(*Error); !ok || e.Code != ErrCodeUnsupported {
+		t.Fatalf("expected an unsupported error from ProvePlonk, got %v", err)
+	}
+	_, err = GetVkPlonk(bytecode, settings)
+	if e, ok := err.(*Error); !ok || e.Code != ErrCodeUnsupported {
+		t.Fatalf("expected an unsupported error from GetVkPlonk, got %v", err)
+	}
+	_, err = VerifyPlonk([]byte{1}, []byte{1}, settings)
+	if e, ok := err.(*Error); !ok || e.Code != ErrCodeUnsupported {
+		t.Fatalf("expected an unsupported error from VerifyPlonk, got %v", err)
+	}
+}
+
+func TestProofForVerifierABI(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+	settings.OracleHashType = HashKeccak
+
+	proof, err := ProveUltraHonkKeccak(bytecode, witnessJSON, settings)
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+	source, err := WriteSolidityVerifier(vk, settings)
+	if err != nil {
+		t.Fatalf("failed to write verifier: %v", err)
+	}
+	if !regexp.MustCompile(`function verify\(\s*bytes calldata \w+,\s*bytes32\[\] calldata \w+\s*\)`).MatchString(source) {
+		t.Fatalf("verifier has no verify(bytes, bytes32[]) function")
+	}
+
+	calldata, err := ProofForVerifier(proof, VerifierSolidityHonk, settings)
+	if err != nil {
+		t.Fatalf("failed to frame proof: %v", err)
+	}
+	fields, err := HonkProofAsFields(proof, settings)
+	if err != nil {
+		t.Fatalf("failed to lay out proof: %v", err)
+	}
+	concat := func(fields []string) []byte {
+		var out []byte
+		for _, f := range fields {
+			b, err := hex.DecodeString(strings.TrimPrefix(f, "0x"))
+			if err != nil || len(b) != 32 {
+				t.Fatalf("invalid field %q", f)
+			}
+			out = append(out, b...)
+		}
+		return out
+	}
+	word := func(offset uint64) uint64 {
+		if offset+32 > uint64(len(calldata)) {
+			t.Fatalf("calldata of %d bytes has no word at %d", len(calldata), offset)
+		}
+		return binary.BigEndian.Uint64(calldata[offset+24 : offset+32])
+	}
+
+	// Head: offsets of the dynamic bytes and bytes32[] arguments.
+	proofOffset, inputsOffset := word(0), word(32)
+	proofLen := word(proofOffset)
+	if proofOffset+32+proofLen > inputsOffset {
+		t.Fatalf("bytes proof argument of %d bytes overlaps publicInputs", proofLen)
+	}
+	if got := calldata[proofOffset+32 : proofOffset+32+proofLen]; !bytes.Equal(got, concat(fields.Proof)) {
+		t.Fatalf("bytes proof argument differs from the proof fields")
+	}
+	inputsLen := word(inputsOffset)
+	if inputsLen != uint64(len(fields.PublicInputs)) {
+		t.Fatalf("%d public inputs in calldata, expected %d", inputsLen, len(fields.PublicInputs))
+	}
+	if got := calldata[inputsOffset+32:]; !bytes.Equal(got, concat(fields.PublicInputs)) {
+		t.Fatalf("bytes32[] publicInputs argument differs from the public inputs")
+	}
+}
+
+func TestVkHashMatchesBb(t *testing.T) {
+	bytecode, _ := loadTestCircuit(t)
+
+	hash, err := VkHash(bytecode, DefaultSettings())
+	if err != nil {
+		t.Fatalf("failed to compute VK hash: %v", err)
+	}
+	if len(hash) != 32 {
+		t.Fatalf("expected a 32-byte VK hash, got %d bytes", len(hash))
+	}
+
+	keccak := DefaultSettings()
+	keccak.OracleHashType = HashKeccak
+	keccakHash, err := VkHash(bytecode, keccak)
+	if err != nil {
+		t.Fatalf("failed to compute Keccak VK hash: %v", err)
+	}
+	if bytes.Equal(hash, keccakHash) {
+		t.Fatalf("Poseidon2 and Keccak VK hashes are equal")
+	}
+
+	// Written by `bb write_vk` with bb's default Poseidon2 settings, by `make test` and the Docker build.
+	expected, err := os.ReadFile("testdata/circuit/target/vk_hash")
+	if err != nil {
+		t.Fatalf("no reference VK hash from the bb CLI, run `make vk-hash`: %v", err)
+	}
+	if !bytes.Equal(hash, expected) {
+		t.Fatalf("VK hash %x differs from bb write_vk's %x", hash, expected)
+	}
+}
+
+func TestConcurrentVkComputedOnce(t *testing.T) {
+	bytecode, _ := loadTestCircuit(t)
+
+	ClearVkCache()
+	before, err := GetMetrics()
+	if err != nil {
+		t.Fatalf("failed to get metrics: %v", err)
+	}
+
+	const callers = 8
+	vks := make([][]byte, callers)
+	errs := make([]error, callers)
+	var wg sync.WaitGroup
+	for i := 0; i < callers; i++ {
+		wg.Add(1)
+		go func(i int) {
+			defer wg.Done()
+			vks[i], errs[i] = GetVkUltraHonk(bytecode, DefaultSettings())
+		}(i)
+	}
+	wg.Wait()
+
+	for i := range vks {
+		if errs[i] != nil {
+			t.Fatalf("caller %d failed to get VK: %v", i, errs[i])
+		}
+		if !bytes.Equal(vks[i], vks[0]) {
+			t.Fatalf("caller %d got a different VK", i)
+		}
+	}
+
+	after, err := GetMetrics()
+	if err != nil {
+		t.Fatalf("failed to get metrics: %v", err)
+	}
+	if computed := after.VksComputed - before.VksComputed; computed != 1 {
+		t.Fatalf("expected the VK to be computed once, got %d", computed)
+	}
+}
+
+func TestAcirStats(t *testing.T) {
+	bytecode, _ := loadTestCircuit(t)
+
+	stats, err := GetAcirStats(bytecode)
+	if err != nil {
+		t.Fatalf("failed to get ACIR stats: %v", err)
+	}
+	if len(stats.Functions) == 0 {
+		t.Fatalf("expected at least one function")
+	}
+	main := stats.Functions[0]
+	if main.Opcodes == 0 {
+		t.Fatalf("expected the main function to have opcodes")
+	}
+	var total uint64
+	for _, n := range main.Kinds {
+		total += n
+	}
+	if total != main.Opcodes {
+		t.Fatalf("opcode kinds add up to %d, expected %d", total, main.Opcodes)
+	}
+}
+
+func TestCompressProofRoundTrip(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+
+	proof, err := ProveUltraHonk(bytecode, witnessJSON, settings)
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+
+	compressed, err := CompressProof(proof)
+	if err != nil {
+		t.Fatalf("failed to compress proof: %v", err)
+	}
+	t.Logf("Proof length: %d, compressed: %d", len(proof), len(compressed))
+
+	decompressed, err := DecompressProof(compressed)
+	if err != nil {
+		t.Fatalf("failed to decompress proof: %v", err)
+	}
+	if !bytes.Equal(decompressed, proof) {
+		t.Fatalf("decompressed proof differs from the original")
+	}
+
+	if _, err := DecompressProof("not base64"); err == nil {
+		t.Fatalf("expected an error for invalid input")
+	}
+}
+
+func TestProofJSONRoundTrip(t *testing.T) {
+	bytecode, witnessJSON := loadTestCircuit(t)
+
+	settings := DefaultSettings()
+
+	proof, err := ProveUltraHonk(bytecode, witnessJSON, settings)
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+
+	proofJSON, err := ProofToJSON(proof)
+	if err != nil {
+		t.Fatalf("failed to convert proof to JSON: %v", err)
+	}
+	if !json.Valid(proofJSON) {
+		t.Fatalf("proof JSON is not valid JSON: %s", proofJSON)
+	}
+
+	back, err := ProofFromJSON(proofJSON)
+	if err != nil {
+		t.Fatalf("failed to convert proof from JSON: %v", err)
+	}
+	if !bytes.Equal(back, proof) {
+		t.Fatalf("proof converted back from JSON differs from the original")
+	}
+
+	if _, err := ProofFromJSON([]byte(`{"proof": "0xzz"}`)); err == nil {
+		t.Fatalf("expected an error for invalid hex")
+	}
+}
+
+func TestFieldEndianness(t *testing.T) {
+	encode := func(value string) []byte {
+		t.Helper()
+		data, err := EncodeWitness(`{"witness": ["` + value + `"]}`)
+		if err != nil {
+			t.Fatalf("failed to encode witness %s: %v", value, err)
+		}
+		return data
+	}
+
+	bigEndian := encode("0x0102")
+	decimal := encode("258")
+	if !bytes.Equal(bigEndian, decimal) {
+		t.Fatalf("big-endian 0x0102 should encode as 258")
+	}
+
+	SetFieldEndianness(true)
+	defer SetFieldEndianness(false)
+
+	littleEndian := encode("0x0201")
+	if !bytes.Equal(littleEndian, decimal) {
+		t.Fatalf("little-endian 0x0201 should encode as 258")
+	}
+	if bytes.Equal(encode("0x0102"), bigEndian) {
+		t.Fatalf("0x0102 should encode differently as little-endian")
+	}
+	if !bytes.Equal(encode("258"), decimal) {
+		t.Fatalf("decimal values should not depend on the endianness")
+	}
+}
+
+func TestProveVerifyHexLittleEndian(t *testing.T) {
+	bytecode, _ := loadTestCircuit(t)
+
+	SetFieldEndianness(true)
+	defer SetFieldEndianness(false)
+
+	// x = 3, y = 9 as full-width little-endian hex.
+	witnessJSON := `{"witness": ["0x` + "03" + strings.Repeat("00", 31) + `", "0x` + "09" + strings.Repeat("00", 31) + `"]}`
+	settings := DefaultSettings()
+
+	proof, err := ProveUltraHonkHex(bytecode, witnessJSON, settings)
+	if err != nil {
+		t.Fatalf("failed to prove: %v", err)
+	}
+	vk, err := GetVkUltraHonk(bytecode, settings)
+	if err != nil {
+		t.Fatalf("failed to get VK: %v", err)
+	}
+	verified, err := VerifyUltraHonkHex(proof, vk, settings)
+	if err != nil {
+		t.Fatalf("failed to verify: %v", err)
+	}
+	if !verified {
+		t.Fatalf("hex proof did not verify with little-endian witnesses enabled")
+	}
+}
+
+func TestFieldModulus(t *testing.T) {
+	if err := SetFieldModulus("0x65"); err != nil {
+		t.Fatalf("failed to set field modulus: %v", err)
+	}
+	defer SetFieldModulus("")
+
+	negative, err := EncodeWitness(`{"witness": ["-1"]}`)
+	if err != nil {
+		t.Fatalf("failed to encode negative witness: %v", err)
+	}
+	reduced, err := EncodeWitness(`{"witness": ["100"]}`)
+	if err != nil {
+		t.Fatalf("failed to encode witness: %v", err)
+	}
+	if !bytes.Equal(negative, reduced) {
+		t.Fatalf("-1 should encode as 100 modulo 101")
+	}
+	if _, err := EncodeWitness(`{"witness": ["101"]}`); err == nil {
+		t.Fatalf("expected an error for a value not below the modulus")
+	}
+
+	if err := SetFieldModulus("not hex"); err == nil {
+		t.Fatalf("expected an error for an invalid modulus")
+	}
+}
+
+func TestEncodeWitnessLargeDecimal(t *testing.T) {
+	// 2^200, past u128.
+	decimal, err := EncodeWitness(`{"witness": ["1606938044258990275541962092341162602522202993782792835301376"]}`)
+	if err != nil {
+		t.Fatalf("failed to encode a decimal above 2^128: %v", err)
+	}
+	hexEncoded, err := EncodeWitness(`{"witness": ["0x0100000000000000000000000000000000000000000000000000"]}`)
+	if err != nil {
+		t.Fatalf("failed to encode hex witness: %v", err)
+	}
+	if !bytes.Equal(decimal, hexEncoded) {
+		t.Fatalf("2^200 encodes differently as decimal and hex")
+	}
+
+	// 2^256 does not fit in 32 bytes.
+	if _, err := EncodeWitness(`{"witness": ["115792089237316195423570985008687907853269984665640564039457584007913129639936"]}`); err == nil {
+		t.Fatalf("expected an error for a decimal wider than 32 bytes")
+	}
+}
+
+func TestFieldRangeCheck(t *testing.T) {
+	// The BN254 scalar field modulus, and a hex value with a stray high bit.
+	for _, value := range []string{
+		"21888242871839275222246405745257275088548364400416034343698204186575808495617",
+		"0x8000000000000000000000000000000000000000000000000000000000000009",
+	} {
+		witnessJSON := `{"witness": ["3", "` + value + `"]}`
+		_, err := EncodeWitness(witnessJSON)
+		if e, ok := err.(*Error); !ok || e.Code != ErrCodeInvalidArgument || !strings.Contains(e.Message, "witness index 1") {
+			t.Fatalf("expected an out of range error for witness index 1, got %v", err)
+		}
+
+		SetFieldRangeCheck(false)
+		_, err = EncodeWitness(witnessJSON)
+		SetFieldRangeCheck(true)
+		if err != nil {
+			t.Fatalf("expected %s to encode with the range check off: %v", value, err)
+		}
+	}
+}
+
+func TestEncodeWitnessSparse(t *testing.T) {
+	dense, err := EncodeWitness(`{"witness": ["3", "9"]}`)
+	if err != nil {
+		t.Fatalf("failed to encode dense witness: %v", err)
+	}
+	keyed, err := EncodeWitness(`{"witness": {"1": "9", "0": "3"}}`)
+	if err != nil {
+		t.Fatalf("failed to encode witness map: %v", err)
+	}
+	if !bytes.Equal(dense, keyed) {
+		t.Fatalf("a witness map with every index encodes differently from the dense witness")
+	}
+
+	// Indices 1 to 6 absent: the map keeps index 7 rather than renumbering it 1.
+	sparse, err := EncodeWitness(`{"witness": {"0": "3", "7": "9"}}`)
+	if err != nil {
+		t.Fatalf("failed to encode sparse witness map: %v", err)
+	}
+	if bytes.Equal(sparse, dense) || !bytes.Contains(sparse, []byte{0x07, 0xc4, 0x20}) {
+		t.Fatalf("sparse witness map lost its explicit index 7: %x", sparse)
+	}
+
+	for _, witnessJSON := range []string{
+		`{"witness": {"0": "3", "0": "9"}}`,
+		`{"witness": {"0": "3", "x": "9"}}`,
+		`{"witness": {"-1": "3"}}`,
+	} {
+		if _, err := EncodeWitness(witnessJSON); err == nil {
+			t.Fatalf("expected an error for %s", witnessJSON)
+		}
+	}
+}
+
+func TestErrorFormatJSON(t *testing.T) {
+	SetErrorFormatJSON(true)
+	defer SetErrorFormatJSON(false)
+
+	_, err := EncodeWitness(`{"witness": ["1", "not a number"]}`)
+	e, ok := err.(*Error)
+	if !ok {
+		t.Fatalf("expected an *Error, got %v", err)
+	}
+	if e.Code != ErrCodeInvalidArgument {
+		t.Fatalf("expected ErrCodeInvalidArgument, got %d", e.Code)
+	}
+	if !strings.HasPrefix(e.Message, "Failed to parse witness[1]") {
+		t.Fatalf("unexpected message %q", e.Message)
+	}
+	if index, ok := e.Context["witness_index"].(float64); !ok || index != 1 {
+		t.Fatalf("expected witness_index 1 in the context, got %v", e.Context)
+	}
+}
+
+func TestErrorInteriorNul(t *testing.T) {
+	// A bb whose --version fails with binary output on stderr.
+	fakeBb := filepath.Join(t.TempDir(), "bb")
+	script := "#!/bin/sh\nprintf 'before\\000after\\n' >&2\nexit 1\n"
+	if err := os.WriteFile(fakeBb, []byte(script), 0o755); err != nil {
+		t.Fatalf("failed to write fake bb: %v", err)
+	}
+
+	_, err := NewBackendHandle(BackendPipe, fakeBb, 0)
+	if e, ok := err.(*Error); !ok || !strings.Contains(e.Message, `before\0after`) {
+		t.Fatalf("expected the nul byte escaped and the message kept, got %v", err)
+	}
+}
+
+func TestEncodeWitnessNumeric(t *testing.T) {
+	numeric, err := EncodeWitness(`{"witness": [3, "0x09", -1]}`)
+	if err != nil {
+		t.Fatalf("failed to encode numeric witness: %v", err)
+	}
+	quoted, err := EncodeWitness(`{"witness": ["3", "0x09", "-1"]}`)
+	if err != nil {
+		t.Fatalf("failed to encode quoted witness: %v", err)
+	}
+	if !bytes.Equal(numeric, quoted) {
+		t.Fatalf("numeric and quoted witness values encode differently")
+	}
+
+	if _, err := EncodeWitness(`{"witness": [1.5]}`); err == nil {
+		t.Fatalf("expected an error for a fractional witness value")
+	}
+}
+
+func TestDecompressProofBase64(t *testing.T) {
+	compressed, err := CompressProof([]byte("not a real proof, just bytes to round trip"))
+	if err != nil {
+		t.Fatalf("failed to compress: %v", err)
+	}
+	raw, err := base64.StdEncoding.DecodeString(compressed)
+	if err != nil {
+		t.Fatalf("compressed output is not standard base64: %v", err)
+	}
+
+	decompressed, err := DecompressProof(base64.RawURLEncoding.EncodeToString(raw))
+	if err != nil {
+		t.Fatalf("failed to decompress URL-safe base64: %v", err)
+	}
+	if string(decompressed) != "not a real proof, just bytes to round trip" {
+		t.Fatalf("URL-safe base64 decompressed to %q", decompressed)
+	}
+
+	if _, err := DecompressProof("abc$"); err == nil || !strings.Contains(err.Error(), "invalid base64") {
+		t.Fatalf("expected an invalid base64 error, got %v", err)
+	}
+	if _, err := DecompressProof(base64.StdEncoding.EncodeToString([]byte("not gzip"))); err == nil ||
+		!strings.Contains(err.Error(), "gzip decompression failed") {
+		t.Fatalf("expected a gzip error, got %v", err)
+	}
+}
+
 func TestBase64(t *testing.T) {
 	s := "H4sIAAAAAAAA/4XMPQ5AMBCF4atMvYVIs9No9S6Gv0SjUInG7S080Ssq3reYDxSlRE9t"
 	_, err := base64.StdEncoding.DecodeString(s)
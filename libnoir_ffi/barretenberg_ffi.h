@@ -5,6 +5,23 @@
 #include <stdint.h>
 #include <stdbool.h>
 
+/* Bumped on every breaking change to the structs or functions below. */
+#define BB_FFI_ABI_VERSION 2
+
+/* Values of BBResult.err_code. */
+enum {
+    BB_ERR_OK = 0,
+    BB_ERR_INVALID_ARGUMENT = 1,
+    BB_ERR_DECODE = 2,
+    BB_ERR_BACKEND = 3,
+    BB_ERR_SERIALIZATION = 4,
+    BB_ERR_TIMEOUT = 5,
+    BB_ERR_PENDING = 6,
+    BB_ERR_CANCELLED = 7,
+    BB_ERR_UNSUPPORTED = 8,
+    BB_ERR_VERSION_MISMATCH = 9,
+};
+
 typedef struct {
     uint8_t *ptr;
     size_t len;
@@ -13,21 +30,219 @@ typedef struct {
 
 typedef struct {
     bool ok;
+    int32_t err_code;
     char *err;
     ByteBuffer data;
 } BBResult;
 
+/* *_b64_gz arguments are gzipped data in standard base64 or, as a fallback,
+ * URL-safe base64, padded or not. Bad base64 fails with BB_ERR_DECODE and
+ * "invalid base64 in ...", bad gzip with "gzip decompression failed: ...". */
+
+/* Every settings_json argument may be NULL or "" to take the settings from
+ * the environment: BB_ORACLE_HASH, BB_ZK and BB_IPA_ACCUMULATION, falling
+ * back to the defaults for unset variables. Explicit JSON ignores them. */
+
+uint32_t bb_abi_version(void);
+
+/* Only for BBResult.data buffers from this library, passed back unchanged.
+ * Anything else is logged and not freed. */
 void bb_free_bytes(ByteBuffer buf);
 void bb_free_err(char *s);
 
+/* With json, BBResult.err holds {"code", "message", "context"} instead of the
+ * bare message; context carries details such as "witness_index" or
+ * "backend_output". Plain messages by default. */
+void bb_set_error_format(bool json);
+
+BBResult bb_configure_backend(
+    const char *backend_type,
+    const char *bb_binary_path,
+    uint32_t threads
+);
+
+BBResult bb_reset_backend(void);
+
+/* Stops the backend, killing the bb subprocess if it doesn't exit in time.
+ * The next call initializes a backend again from BB_BACKEND_TYPE. */
+BBResult bb_shutdown(void);
+
+/* Resolves and runs the bb binary; returns JSON {"path", "version"} */
+BBResult bb_check_binary(void);
+
+/* JSON array of directories or files searched for the bb binary, in order;
+ * "null" restores the defaults. BB_BINARY_PATH takes priority. */
+BBResult bb_set_binary_search_paths(const char *paths_json);
+
+/* Timeout for each pipe backend call, counted from when it holds the backend;
+ * 0 disables it. Native backend calls are not timed. */
+void bb_set_call_timeout_ms(uint32_t timeout_ms);
+
+/* Spawning bb is tried up to `attempts` times, waiting delay_ms before the
+ * first retry and doubling it after each (default 3 attempts, 100 ms). */
+void bb_set_spawn_retry(uint32_t attempts, uint32_t delay_ms);
+
+/* Pins the pipe backend's bb process to CPUs 0..threads-1 (Linux only), for
+ * backends started afterwards. Threads default to BB_THREADS, else the CPU
+ * count. */
+void bb_set_thread_affinity(bool enabled);
+
 BBResult bb_init_srs_from_bytecode(const char *bytecode_b64_gz);
 
+/* Loads SRS points for circuits of up to max_gates gates (rounded up to a
+ * power of two, returned as a little-endian u32) from the SRS directory into
+ * the backend; fails if it holds fewer. Larger circuits then fail until the
+ * SRS is loaded again. A no-op when the backend already loaded that many
+ * points. */
+BBResult bb_ensure_srs(uint32_t max_gates);
+
+/* Directory the SRS is loaded from, for pipe backends started afterwards;
+ * NULL or "" restores BB_CRS_PATH, then the backend default. The native
+ * backend reads CRS_PATH from the environment instead. */
+BBResult bb_set_srs_path(const char *path);
+
+/* JSON {"path", "source": "set"|"env"|"default", "loaded", "points"}, where
+ * points is the largest size loaded with bb_init_srs_from_bytecode. */
+BBResult bb_srs_status(void);
+
+void bb_set_field_range_check(bool enabled);
+
+/* Hex modulus for the witness range check and negative values, for circuits
+ * over another <= 256-bit field; NULL or "" restores BN254 */
+BBResult bb_set_field_modulus(const char *modulus_hex);
+
+/* Byte order of 0x hex witness values; big-endian (false) by default.
+ * Public inputs are always big-endian. */
+void bb_set_field_endianness(bool little_endian);
+
+void bb_clear_vk_cache(void);
+
+/* A witness whose length doesn't match the circuit fails with
+ * BB_ERR_INVALID_ARGUMENT ("Witness length mismatch: expected X, got Y") */
 BBResult bb_prove_ultrahonk(
     const char *bytecode_b64_gz,
     const char *witness_json,
     const char *settings_json
 );
 
+/* Same as bb_prove_ultrahonk, also returning the VK. Buffer layout:
+ *   proof_len (u32 LE), msgpack proof response (proof_len bytes), VK bytes */
+BBResult bb_prove_ultrahonk_with_vk_out(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+/* Same as bb_prove_ultrahonk, with the witness JSON base64 gzipped like the
+ * bytecode */
+BBResult bb_prove_ultrahonk_gz(
+    const char *bytecode_b64_gz,
+    const char *witness_json_b64_gz,
+    const char *settings_json
+);
+
+/* Same as bb_prove_ultrahonk, with oracle_hash_type forced to keccak (for
+ * Solidity verifiers) or poseidon2; the other settings are kept */
+BBResult bb_prove_ultrahonk_keccak(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+BBResult bb_prove_ultrahonk_poseidon(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+/* Same as bb_prove_ultrahonk, with the bytecode already decompressed */
+BBResult bb_prove_ultrahonk_raw_bytecode(
+    const uint8_t *bytecode_ptr,
+    size_t bytecode_len,
+    const char *witness_json,
+    const char *settings_json
+);
+
+/* Same as bb_prove_ultrahonk, proving with a VK the caller already has
+ * instead of computing one */
+BBResult bb_prove_ultrahonk_with_vk(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+/* Proving keys: bb derives the PK inside each prove call and cannot export
+ * or import one, so both fail with BB_ERR_UNSUPPORTED. */
+BBResult bb_compute_pk(const char *bytecode_b64_gz, const char *settings_json);
+BBResult bb_prove_ultrahonk_with_pk(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const uint8_t *pk_ptr,
+    size_t pk_len,
+    const char *settings_json
+);
+
+BBResult bb_prove_ultrahonk_stack(
+    const char *bytecode_b64_gz,
+    const char *stack_json,
+    const char *settings_json
+);
+
+BBResult bb_prove_ultrahonk_from_witness_gz(
+    const char *bytecode_b64_gz,
+    const char *witness_gz_b64,
+    const char *settings_json
+);
+
+/* Proves with a witness stack as returned by bb_encode_witness (format byte
+ * 2, then msgpack), passed to the backend unparsed. */
+BBResult bb_prove_ultrahonk_witness_blob(
+    const char *bytecode_b64_gz,
+    const uint8_t *witness_blob_ptr,
+    size_t witness_blob_len,
+    const char *settings_json
+);
+
+/* Reads the bytecode (nargo .json artifact, or gzipped ACIR) and witness
+ * (nargo witness.gz, or .json) from disk */
+BBResult bb_prove_ultrahonk_from_file(
+    const char *bytecode_path,
+    const char *witness_path,
+    const char *settings_json
+);
+
+/*
+ * Returns, with little-endian u32 lengths:
+ *   proof_len, proof (proof_len bytes),
+ *   num_public_inputs, public inputs (num_public_inputs * 32 bytes)
+ */
+BBResult bb_prove_ultrahonk_raw(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+BBResult bb_prove_ultrahonk_batch(
+    const char *bytecode_b64_gz,
+    const char *witnesses_json,
+    const char *settings_json
+);
+
+/* Prove and return a self-contained msgpack bundle {proof, vk, settings,
+ * producer}: the proof response, its VK, the resolved settings and the
+ * backend version. bb_verify_bundle verifies it with no other inputs and
+ * reports like bb_verify_ultrahonk_ex, except that a rejected bundle from
+ * another backend version fails with BB_ERR_VERSION_MISMATCH. */
+BBResult bb_prove_ultrahonk_bundle(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+BBResult bb_verify_bundle(const uint8_t *bundle_ptr, size_t bundle_len);
+
 BBResult bb_get_vk_ultrahonk(
     const char *bytecode_b64_gz,
     const char *settings_json
@@ -41,4 +256,408 @@ bool bb_verify_ultrahonk(
     const char *settings_json
 );
 
+BBResult bb_verify_ultrahonk_ex(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+/* Verify against the VK computed from the circuit's bytecode rather than a
+ * caller-supplied one. Reports like bb_verify_ultrahonk_ex. */
+BBResult bb_verify_ultrahonk_strict(
+    const char *bytecode_b64_gz,
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len,
+    const char *settings_json
+);
+
+/* Verify a msgpack array of proof responses against one VK. Returns one byte
+ * per proof, in order: 1 verified, 0 rejected, 2 could not be verified.
+ * Only a malformed array, VK or settings fails the whole call. */
+BBResult bb_verify_ultrahonk_batch(
+    const uint8_t *proofs_msgpack_ptr,
+    size_t proofs_msgpack_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+/* Checks a proof buffer is well formed without verifying it; fails with
+ * BB_ERR_DECODE if it is truncated or corrupt */
+BBResult bb_validate_proof_blob(const uint8_t *proof_msgpack_ptr, size_t proof_msgpack_len);
+
+/* Returns: u32 LE count, followed by count 32-byte big-endian field elements. */
+BBResult bb_proof_public_inputs(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len
+);
+
+/* Whether a proof and VK fit together by their shapes (public input count,
+ * proof length for the circuit size), without verifying. Returns JSON
+ * {"compatible": bool, "reasons": [..]} */
+BBResult bb_proof_vk_compatible(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len
+);
+
+/* Verifies, then returns the public inputs that verified in the layout of
+ * bb_proof_public_inputs; a proof that doesn't verify fails with
+ * BB_ERR_INVALID_ARGUMENT. */
+BBResult bb_verify_ultrahonk_recover(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+/* Returns JSON: {"proof": "0x..", "public_inputs": ["0x..", ...]} */
+BBResult bb_proof_as_fields(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len
+);
+
+/* Solidity Honk verifier layout, JSON {"proof": ["0x..", ..],
+ * "public_inputs": ["0x..", ..]}: proof is the 16 pairing point fields, then
+ * the proof fields in transcript order, concatenated into the bytes proof
+ * argument of verify; public_inputs are the circuit's own.
+ * Settings must use the keccak oracle without IPA accumulation. */
+BBResult bb_honk_proof_as_fields(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len,
+    const char *settings_json
+);
+
+/* Proof framed as an on-chain verifier's calldata after the selector.
+ * verifier "solidity_honk": ABI-encoded (bytes proof, bytes32[] publicInputs)
+ * laid out as by bb_honk_proof_as_fields; "solidity_plonk" fails with
+ * BB_ERR_UNSUPPORTED. */
+BBResult bb_proof_for_verifier(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len,
+    const char *verifier,
+    const char *settings_json
+);
+
+/* The proof response as JSON with the same field names, byte fields as "0x"
+ * hex: {"public_inputs": ["0x..", ..], "proof": ["0x..", ..]} */
+BBResult bb_proof_msgpack_to_json(
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len
+);
+
+/* Inverse of bb_proof_msgpack_to_json; returns the msgpack proof */
+BBResult bb_proof_json_to_msgpack(const char *proof_json);
+
+/* Prove returning JSON {"proof": "0x..", "public_inputs": ["0x..", ..]} */
+BBResult bb_prove_ultrahonk_hex(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+/* Verifies the JSON returned by bb_prove_ultrahonk_hex; data is 1 byte:
+ * 1 verified, 0 rejected */
+BBResult bb_verify_ultrahonk_hex(
+    const char *proof_json,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+BBResult bb_write_solidity_verifier(
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+/* Returns JSON: {"num_gates", "subgroup_size", "num_acir_opcodes", "num_public_inputs"} */
+BBResult bb_circuit_info(
+    const char *bytecode_b64_gz,
+    const char *settings_json
+);
+
+/* Opcode counts read from the ACIR bytecode, without the backend. Returns
+ * JSON: {"functions": [{"name", "opcodes", "kinds": {"AssertZero": n, ..},
+ * "black_box_calls": {"RANGE": n, ..}}, ..]}, main function first */
+BBResult bb_acir_stats(const char *bytecode_b64_gz);
+
+/* Approximate peak proving memory, linear in the subgroup size. Returns
+ * JSON: {"bytes", "subgroup_size"} */
+BBResult bb_estimate_memory(const char *bytecode_b64_gz, const char *settings_json);
+
+/* Returns JSON: {"proof", "public_inputs", "aggregation_object", "vk"}, all hex field arrays */
+BBResult bb_prove_ultrahonk_recursive(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+/* Returns a JSON array of hex field elements */
+BBResult bb_vk_as_fields(const uint8_t *vk_ptr, size_t vk_len);
+
+/* VK hash (32 bytes) and VK fields (JSON array of hex strings) straight from
+ * the bytecode. bb_vk_hash is the vk_hash bb write_vk produces for these
+ * settings. */
+BBResult bb_vk_hash(const char *bytecode_b64_gz, const char *settings_json);
+/* Alias of bb_vk_hash, kept for callers using the UltraHonk name */
+BBResult bb_vk_hash_ultrahonk(const char *bytecode_b64_gz, const char *settings_json);
+BBResult bb_vk_fields(const char *bytecode_b64_gz, const char *settings_json);
+
+/* Returns JSON: {"backend", "configured", "bb_path", "version",
+ * "ffi_version"}. backend is the one running: with BB_BACKEND_FALLBACK=pipe
+ * a native backend that fails to start is replaced by the pipe backend. */
+BBResult bb_backend_version(void);
+
+/* Returns JSON: {"backends", "proof_systems", "solidity_verifier"} */
+BBResult bb_capabilities(void);
+
+/* Receives the result of an async call; the callee owns and must free it. */
+typedef void (*BBResultCallback)(void *user_data, BBResult result);
+
+void bb_prove_ultrahonk_async(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json,
+    BBResultCallback callback,
+    void *user_data
+);
+
+/* Job API: bb_prove_begin starts a proof and returns its id (0 only on an
+ * internal failure). bb_prove_poll fails with BB_ERR_PENDING until the job
+ * is done, then returns its result and releases the id; bb_prove_cancel aborts it. */
+uint64_t bb_prove_begin(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+BBResult bb_prove_poll(uint64_t job_id);
+BBResult bb_prove_cancel(uint64_t job_id);
+
+/* Read-only view of the jobs not yet released: JSON {"queued", "running",
+ * "completed", "jobs": [{"id", "state", "age_ms"}]}, ordered by id. */
+BBResult bb_jobs_status(void);
+
+/* Proving latency: bb_last_timing returns JSON {"vk_ms", "prove_ms",
+ * "total_ms"} for the last proof made while timing was enabled, or null. */
+void bb_enable_timing(bool enabled);
+BBResult bb_last_timing(void);
+
+/* Log levels passed to the log callback. */
+enum {
+    BB_LOG_ERROR = 0,
+    BB_LOG_WARN = 1,
+    BB_LOG_INFO = 2,
+    BB_LOG_DEBUG = 3,
+};
+
+/* Receives one log line; msg is only valid during the call. May be invoked
+ * from any thread. Pass NULL to bb_set_log_callback to remove it. Lines from
+ * the stderr of bb subprocesses are forwarded at BB_LOG_DEBUG; without a
+ * callback they are only kept for bb_last_backend_output. */
+typedef void (*BBLogCallback)(int32_t level, const char *msg);
+void bb_set_log_callback(BBLogCallback callback);
+
+/* Phases passed to the progress callback. */
+enum {
+    BB_PHASE_COMPUTING_VK = 0,
+    BB_PHASE_PROVING = 1,
+    BB_PHASE_DONE = 2,
+};
+
+/* Receives each phase of an UltraHonk proof with a rough percentage done
+ * (0, 20, 100). COMPUTING_VK is skipped on a VK cache hit; there is no
+ * progress within a phase. Pass NULL to remove it. */
+typedef void (*BBProgressCallback)(int32_t phase, float pct);
+void bb_set_progress_callback(BBProgressCallback callback);
+
+/* Returns JSON counters: {"proofs_started", "proofs_succeeded",
+ * "proofs_failed", "vks_computed", "verifications_run", "backend_calls",
+ * "backend_restarts"} */
+BBResult bb_metrics_json(void);
+
+/* Maximum decompressed size of gzipped bytecode and witnesses; 0 restores
+ * the default of 512 MiB. */
+void bb_set_max_bytecode_size(uint64_t max_bytes);
+
+/* Proves and verifies an embedded test circuit. Returns JSON timing:
+ * {"vk_ms", "prove_ms", "verify_ms", "total_ms"} */
+BBResult bb_self_test(void);
+
+/* Starts the backend and makes one cheap round trip so the first proof
+ * doesn't pay for it. Returns JSON timing:
+ * {"spawn_ms", "round_trip_ms", "total_ms"} */
+BBResult bb_warmup(void);
+
+/* UltraPlonk: removed from bb, so all three fail with BB_ERR_UNSUPPORTED.
+ * The signatures mirror the UltraHonk ones; Plonk needs an older bb. */
+BBResult bb_prove_plonk(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+BBResult bb_get_vk_plonk(const char *bytecode_b64_gz, const char *settings_json);
+BBResult bb_verify_plonk(
+    const uint8_t *proof_ptr,
+    size_t proof_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+/* Configurations this library supports, as JSON:
+ * {"oracle_hashes": [..], "proof_systems": [{"name", "prove", "verify"}],
+ *  "zk"}. Does not start a backend. */
+BBResult bb_supported_flavors(void);
+
+/* MegaHonk: only the VK can be computed standalone. MegaHonk circuits are
+ * proven inside a ClientIVC stack, so prove/verify fail with
+ * BB_ERR_UNSUPPORTED. */
+BBResult bb_get_vk_megahonk(const char *bytecode_b64_gz);
+BBResult bb_prove_megahonk(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+BBResult bb_verify_megahonk(
+    const uint8_t *proof_ptr,
+    size_t proof_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len
+);
+
+/* ClientIVC: start a session with settings {"num_circuits": n}, accumulate n
+ * circuit executions, then prove. Returns the msgpack proof response. */
+BBResult bb_civc_start(const char *settings_json);
+BBResult bb_civc_accumulate(const char *bytecode_b64_gz, const char *witness_json);
+BBResult bb_civc_prove(void);
+
+/* Verify from components: raw proof bytes and public inputs, each as
+ * concatenated 32-byte fields. public_inputs may be NULL with length 0.
+ * The _ex variant reports like bb_verify_ultrahonk_ex. */
+bool bb_verify_ultrahonk_parts(
+    const uint8_t *proof_ptr,
+    size_t proof_len,
+    const uint8_t *public_inputs_ptr,
+    size_t public_inputs_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+BBResult bb_verify_ultrahonk_parts_ex(
+    const uint8_t *proof_ptr,
+    size_t proof_len,
+    const uint8_t *public_inputs_ptr,
+    size_t public_inputs_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+/* Persist the VK cache. Load ignores files written by another backend or bb
+ * version and returns the number of VKs loaded as a little-endian u32. */
+BBResult bb_vk_cache_save(const char *path);
+BBResult bb_vk_cache_load(const char *path);
+
+/* Streaming witness: the callback writes witness value `index` (hex or
+ * decimal, nul-terminated) into out_value, a zeroed buffer of
+ * BB_WITNESS_VALUE_BUF_LEN bytes. Called for indices 0..witness_len-1 in
+ * order, on the calling thread. */
+#define BB_WITNESS_VALUE_BUF_LEN 80
+typedef void (*BBWitnessCallback)(void *user_data, uint32_t index, char *out_value);
+
+BBResult bb_prove_ultrahonk_witness_stream(
+    const char *bytecode_b64_gz,
+    uint32_t witness_len,
+    BBWitnessCallback callback,
+    void *user_data,
+    const char *settings_json
+);
+
+/* Chunked proof output: the callback gets the msgpack proof in order, in
+ * chunks of at most BB_PROOF_CHUNK_SIZE bytes valid only during the call,
+ * then once with len 0 to mark the end. Called on the calling thread after
+ * proving, never on error; the BBResult carries no data. */
+#define BB_PROOF_CHUNK_SIZE 65536
+typedef void (*BBChunkCallback)(void *user_data, const uint8_t *data, size_t len);
+
+BBResult bb_prove_ultrahonk_chunked(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json,
+    BBChunkCallback callback,
+    void *user_data
+);
+
+/* Returns JSON {"settings": {...}, "warnings": [...]}: the fully resolved
+ * settings and warnings for defaulted or unknown keys. */
+BBResult bb_validate_settings(const char *settings_json);
+
+/* Returns JSON {"proof_bytes", "vk_bytes", "public_inputs_count"} for the
+ * circuit and settings, without a witness. The first call for a circuit
+ * and settings proves it once with an all-zero witness to measure the proof */
+BBResult bb_proof_sizes(const char *bytecode_b64_gz, const char *settings_json);
+
+/* Gzip + base64 a proof for storage or transport, and back */
+BBResult bb_compress_proof(const uint8_t *proof_ptr, size_t proof_len);
+BBResult bb_decompress_proof(const char *proof_b64_gz);
+
+/* Witness bytes as sent to the backend by the prove functions */
+BBResult bb_encode_witness(const char *witness_json);
+
+/* stderr of the global backend's bb subprocess during its last call; waits
+ * for a call in progress. Kept per backend, not per caller: with concurrent
+ * callers use a handle each and bb_handle_last_backend_output */
+BBResult bb_last_backend_output(void);
+
+/* Checks the arguments of bb_prove_ultrahonk without calling the backend */
+BBResult bb_prove_validate(
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+/* Number of public inputs of the circuit, as a little-endian u32 */
+BBResult bb_public_input_count(const char *bytecode_b64_gz, const char *settings_json);
+
+/* Independent backends: each handle owns its own bb subprocess (or native
+ * context), so calls on different handles run in parallel. config_json is
+ * {"backend_type", "bb_path", "threads"}, all optional. bb_backend_new returns
+ * NULL on failure; the error goes to the log callback. */
+typedef struct BBHandle BBHandle;
+
+BBHandle *bb_backend_new(const char *config_json);
+/* Shuts the handle's backend down once a call in progress returns; later
+ * calls on the handle fail with BB_ERR_INVALID_ARGUMENT */
+void bb_backend_free(BBHandle *handle);
+
+BBResult bb_handle_prove_ultrahonk(
+    const BBHandle *handle,
+    const char *bytecode_b64_gz,
+    const char *witness_json,
+    const char *settings_json
+);
+
+/* Same result as bb_verify_ultrahonk_ex */
+BBResult bb_handle_verify_ultrahonk(
+    const BBHandle *handle,
+    const uint8_t *proof_msgpack_ptr,
+    size_t proof_msgpack_len,
+    const uint8_t *vk_ptr,
+    size_t vk_len,
+    const char *settings_json
+);
+
+BBResult bb_handle_get_vk_ultrahonk(
+    const BBHandle *handle,
+    const char *bytecode_b64_gz,
+    const char *settings_json
+);
+
+BBResult bb_handle_last_backend_output(const BBHandle *handle);
+
 #endif /* NOIR_FFI_H */
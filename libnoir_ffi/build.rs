@@ -0,0 +1,17 @@
+//! Embeds the compiled test circuit used by `bb_self_test`. It is produced by
+//! `nargo compile` in `testdata/circuit`; when it is missing the library still
+//! builds, and `bb_self_test` reports that it has no fixture.
+
+use std::path::PathBuf;
+
+fn main() {
+    let fixture = std::env::var("BB_SELF_TEST_CIRCUIT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("../testdata/circuit/target/circuit.json"));
+    println!("cargo:rerun-if-env-changed=BB_SELF_TEST_CIRCUIT");
+    println!("cargo:rerun-if-changed={}", fixture.display());
+
+    let contents = std::fs::read_to_string(&fixture).unwrap_or_default();
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("self_test_circuit.json");
+    std::fs::write(out, contents).unwrap();
+}
@@ -1,78 +1,710 @@
-use std::{ffi::{CStr, CString}, os::raw::c_char, ptr::null_mut};
-use once_cell::sync::OnceCell;
+use std::{ffi::{c_void, CStr, CString}, os::raw::c_char, ptr::null_mut};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use barretenberg_rs::BarretenbergApi;
-use barretenberg_rs::backends::PipeBackend;
 #[cfg(feature = "native-backend")]
 use barretenberg_rs::backends::FfiBackend;
-use barretenberg_rs::generated_types::{CircuitInput, CircuitInputNoVK, ProofSystemSettings, CircuitProveResponse, Command};
+use barretenberg_rs::generated_types::{CircuitInput, CircuitInputNoVK, ProofSystemSettings, CircuitProveResponse, CircuitComputeVkResponse, CircuitInfoResponse, Command};
 use base64::{Engine as _, engine::general_purpose};
 use std::io::Read;
 use flate2::read::GzDecoder;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use num_bigint::BigUint;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+mod logging;
+mod metrics;
+#[cfg(unix)]
+mod pipe;
+mod process;
+mod progress;
+mod timing;
+mod vk_cache;
+use vk_cache::VkCache;
+
+// The pipe backend talks to `bb` through Unix pipes and signals.
+#[cfg(not(any(unix, feature = "native-backend")))]
+compile_error!("The pipe backend needs a Unix platform: enable the native-backend feature to build elsewhere");
 
 enum ApiEnum {
-    Pipe(BarretenbergApi<PipeBackend>),
+    #[cfg(unix)]
+    Pipe(BarretenbergApi<pipe::BbProcess>),
     #[cfg(feature = "native-backend")]
     Native(BarretenbergApi<FfiBackend>),
 }
 
-static BB_API: OnceCell<std::sync::Mutex<ApiEnum>> = OnceCell::new();
+impl ApiEnum {
+    /// Whether this is the pipe backend, the only one with a `bb` subprocess.
+    #[cfg(unix)]
+    fn is_pipe(&self) -> bool {
+        matches!(self, ApiEnum::Pipe(_))
+    }
+
+    #[cfg(not(unix))]
+    fn is_pipe(&self) -> bool {
+        false
+    }
+}
+
+/// How the backend was created, kept so it can be respawned identically.
+struct BackendConfig {
+    backend_type: String,
+    bb_path: Option<String>,
+    threads: Option<usize>,
+}
+
+struct Backend {
+    api: ApiEnum,
+    config: BackendConfig,
+    /// Thread count the pipe backend's `bb` subprocess was started with,
+    /// which differs from the configured one while a call overrides it.
+    threads: usize,
+    /// Pid of the `bb` subprocess when using the pipe backend.
+    pid: Option<u32>,
+    /// Set once the backend is known to be unusable, e.g. a call panicked
+    /// while holding it or its subprocess could not be respawned. The next
+    /// `get_api()` rebuilds it.
+    poisoned: bool,
+    /// ClientIVC session in progress. Its accumulator lives inside the
+    /// backend, so it is lost whenever the backend is respawned.
+    civc: Option<CivcSession>,
+    /// Largest SRS size loaded through `bb_init_srs_from_bytecode`, 0 if none.
+    srs_points: u32,
+    /// What the pipe backend's `bb` wrote to stderr during the current or
+    /// last call, kept across respawns.
+    output: Arc<logging::Output>,
+}
+
+struct CivcSession {
+    num_circuits: u32,
+    accumulated: u32,
+}
+
+impl Backend {
+    fn new(config: BackendConfig) -> Result<Self, FfiError> {
+        let threads = config.threads.unwrap_or_else(default_threads);
+        let output = Arc::default();
+        let (api, pid) = Self::spawn(&config, threads, &output)?;
+        Ok(Backend { api, config, threads, pid, poisoned: false, civc: None, srs_points: 0, output })
+    }
+
+    fn spawn(config: &BackendConfig, threads: usize, output: &Arc<logging::Output>) -> Result<(ApiEnum, Option<u32>), FfiError> {
+        let (api, pid) = create_api(&config.backend_type, config.bb_path.clone(), threads, output)?;
+        if THREAD_AFFINITY.load(Ordering::Relaxed) {
+            match pid {
+                Some(pid) if process::pin_to_cpus(pid, threads) => {
+                    logging::log(logging::LogLevel::Debug, format!("Pinned bb pid {} to CPUs 0-{}", pid, threads - 1))
+                }
+                Some(pid) => logging::log(logging::LogLevel::Warn, format!("Failed to pin bb pid {} to CPUs", pid)),
+                // The native backend's threads run in the caller's process,
+                // which isn't ours to pin.
+                None => logging::log(logging::LogLevel::Warn, "Thread affinity only applies to the pipe backend's bb process"),
+            }
+        }
+        logging::log(
+            logging::LogLevel::Debug,
+            format!("Started {} backend{}", config.backend_type, pid.map(|p| format!(", bb pid {}", p)).unwrap_or_default()),
+        );
+        Ok((api, pid))
+    }
+
+    /// Stops the backend.
+    fn shutdown(self) {
+        stop_api(self.api, self.pid);
+    }
+
+    /// Replaces the current backend with a freshly spawned one running the
+    /// same number of threads.
+    fn reconnect(&mut self) -> Result<(), FfiError> {
+        self.respawn(self.threads)
+    }
+
+    /// Replaces the current backend with a freshly spawned one running
+    /// `threads` threads. The old one is only stopped once the new one is up.
+    fn respawn(&mut self, threads: usize) -> Result<(), FfiError> {
+        metrics::inc(&metrics::BACKEND_RESTARTS);
+        self.replace(threads)
+    }
+
+    /// `respawn` without counting it as a restart.
+    fn replace(&mut self, threads: usize) -> Result<(), FfiError> {
+        let (api, pid) = Self::spawn(&self.config, threads, &self.output)?;
+        self.threads = threads;
+        let old_api = std::mem::replace(&mut self.api, api);
+        let old_pid = std::mem::replace(&mut self.pid, pid);
+        stop_api(old_api, old_pid);
+        self.poisoned = false;
+        self.civc = None;
+        self.srs_points = 0;
+        Ok(())
+    }
+
+    /// Has the pipe backend serve the next command with `threads` threads,
+    /// restarting `bb` if it runs with another count, which also drops the
+    /// SRS it loaded. Refused while a ClientIVC session is open, as the
+    /// restart would lose it. The native backend sizes its pool from the
+    /// environment once, so it is left alone.
+    fn use_threads(&mut self, threads: usize) -> Result<(), FfiError> {
+        if self.threads == threads || !self.api.is_pipe() {
+            return Ok(());
+        }
+        if self.civc.is_some() {
+            return Err(FfiError::invalid_argument(
+                "Cannot change the thread count while a ClientIVC session is open: restarting bb would drop it",
+            ));
+        }
+        self.replace(threads)
+    }
+
+    /// Thread count the backend was configured with.
+    fn configured_threads(&self) -> usize {
+        self.config.threads.unwrap_or_else(default_threads)
+    }
+}
+
+/// How long `stop_api` waits for the `bb` subprocess to exit.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Drops `api`. For the pipe backend this closes the `bb` subprocess's stdin,
+/// which makes it exit; it gets `SHUTDOWN_GRACE` to do so before it is
+/// killed, and is reaped either way.
+fn stop_api(api: ApiEnum, pid: Option<u32>) {
+    drop(api);
+    let Some(pid) = pid else {
+        return;
+    };
+    if !process::wait_exit(pid, SHUTDOWN_GRACE) {
+        logging::log(logging::LogLevel::Warn, format!("bb pid {} did not exit, killing it", pid));
+        process::kill(pid);
+        process::wait_exit(pid, SHUTDOWN_GRACE);
+    }
+}
+
+/// A backend behind its lock, `None` until it is first used or configured.
+type BackendSlot = std::sync::Mutex<Option<Backend>>;
+
+/// The global backend used by every call outside a handle.
+static BB_API: BackendSlot = std::sync::Mutex::new(None);
+
+thread_local! {
+    /// Slot of the handle whose entrypoint runs on this thread; `None` means
+    /// `BB_API`.
+    static CURRENT_SLOT: std::cell::Cell<Option<&'static BackendSlot>> = const { std::cell::Cell::new(None) };
+}
+
+fn current_slot() -> &'static BackendSlot {
+    CURRENT_SLOT.with(|c| c.get()).unwrap_or(&BB_API)
+}
+
+/// Runs `f` with backend calls on this thread going to `slot`.
+fn with_slot<T>(slot: &'static BackendSlot, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<&'static BackendSlot>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT_SLOT.with(|c| c.set(self.0));
+        }
+    }
+
+    let _restore = Restore(CURRENT_SLOT.with(|c| c.replace(Some(slot))));
+    f()
+}
+
+/// Locks the current backend slot. A panic while the lock was held leaves
+/// the backend in an unknown state, so it is marked poisoned for `get_api()`
+/// to rebuild rather than failing every later call.
+fn lock_backend() -> std::sync::MutexGuard<'static, Option<Backend>> {
+    let slot = current_slot();
+    slot.lock().unwrap_or_else(|e| {
+        slot.clear_poison();
+        let mut slot = e.into_inner();
+        if let Some(backend) = slot.as_mut() {
+            backend.poisoned = true;
+        }
+        slot
+    })
+}
+
+/// Lock on an initialized backend, as handed out by `get_api()`.
+struct BackendGuard(std::sync::MutexGuard<'static, Option<Backend>>);
+
+impl std::ops::Deref for BackendGuard {
+    type Target = Backend;
+
+    fn deref(&self) -> &Backend {
+        self.0.as_ref().expect("backend initialized")
+    }
+}
+
+impl std::ops::DerefMut for BackendGuard {
+    fn deref_mut(&mut self) -> &mut Backend {
+        self.0.as_mut().expect("backend initialized")
+    }
+}
+
+const VK_CACHE_CAPACITY: usize = 64;
+
+/// Lookups take the read lock only, so cache hits from many threads proceed
+/// together and never wait for the backend.
+static VK_CACHE: Lazy<std::sync::RwLock<VkCache>> = Lazy::new(|| std::sync::RwLock::new(VkCache::new(VK_CACHE_CAPACITY)));
 
-fn find_bb_binary() -> String {
+/// VK computations in progress, one lock per cache key: concurrent misses for
+/// the same circuit wait on the first caller's computation instead of each
+/// calling the backend.
+static VK_IN_FLIGHT: Lazy<std::sync::Mutex<HashMap<vk_cache::CacheKey, Arc<std::sync::Mutex<()>>>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Directories or files searched for the `bb` binary, in order; `None` uses
+/// `default_binary_search_paths`.
+static BINARY_SEARCH_PATHS: std::sync::Mutex<Option<Vec<String>>> = std::sync::Mutex::new(None);
+
+/// The installer locations under the home directory. Without a usable home
+/// directory (e.g. `HOME` unset or empty and no passwd entry) there are none,
+/// rather than paths relative to `/`.
+fn default_binary_search_paths() -> Vec<String> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    vec![
+        // New installer
+        home.join(".aztec").join("bin").to_string_lossy().into_owned(),
+        // Old installer
+        home.join(".bb").to_string_lossy().into_owned(),
+    ]
+}
+
+/// Resolves the `bb` binary: `BB_BINARY_PATH` if set, then `bb` on `PATH`
+/// when no search paths are configured, then each search path in order. A
+/// directory entry is tried as `<dir>/bb`. The error lists every candidate.
+fn find_bb_binary() -> Result<String, FfiError> {
     if let Ok(p) = std::env::var("BB_BINARY_PATH") {
-        return p;
+        return Ok(p);
     }
-    
-    // Check if 'bb' is in PATH
-    if which::which("bb").is_ok() {
-        return "bb".to_string();
+
+    let configured = BINARY_SEARCH_PATHS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let mut tried = Vec::new();
+    if configured.is_none() {
+        if let Ok(p) = which::which("bb") {
+            return Ok(p.to_string_lossy().into_owned());
+        }
+        tried.push("bb on PATH".to_string());
     }
 
-    let home = std::env::var("HOME").unwrap_or_default();
-    
-    // Check ~/.aztec/bin/bb (new installer)
-    let aztec_path = format!("{}/.aztec/bin/bb", home);
-    if std::path::Path::new(&aztec_path).exists() {
-        return aztec_path;
+    for entry in configured.unwrap_or_else(default_binary_search_paths) {
+        let path = std::path::Path::new(&entry);
+        let candidate = if path.is_dir() { path.join("bb") } else { path.to_path_buf() };
+        if candidate.is_file() {
+            return Ok(candidate.to_string_lossy().into_owned());
+        }
+        tried.push(candidate.to_string_lossy().into_owned());
+    }
+    logging::log(logging::LogLevel::Debug, format!("bb binary candidates tried: {}", tried.join(", ")));
+
+    Err(FfiError::backend(format!("bb binary not found, tried: {}", tried.join(", "))))
+}
+
+/// Sets the ordered list of directories or files searched for the `bb`
+/// binary, as a JSON array of strings; `null` restores the defaults (`PATH`,
+/// `~/.aztec/bin`, `~/.bb`). `BB_BINARY_PATH` still takes priority. Affects
+/// backends spawned afterwards.
+#[no_mangle]
+pub extern "C" fn bb_set_binary_search_paths(paths_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let paths_str = unsafe { cstr_to_string(paths_json) }?;
+            let paths: Option<Vec<String>> = serde_json::from_str(&paths_str).map_err(FfiError::invalid_argument)?;
+            *BINARY_SEARCH_PATHS.lock().unwrap_or_else(|e| e.into_inner()) = paths;
+            Ok(())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Runs `bb --version` and returns its trimmed output.
+fn bb_binary_version(bb_path: &str) -> Result<String, FfiError> {
+    let output = std::process::Command::new(bb_path)
+        .arg("--version")
+        .output()
+        .map_err(|e| FfiError::backend(format!("Failed to run {} --version: {}", bb_path, e)))?;
+    if !output.status.success() {
+        return Err(FfiError::backend(format!(
+            "{} --version exited with {}: {}",
+            bb_path,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
     }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Resolves the `bb` binary (`bb_path`, else `find_bb_binary`), checks that it
+/// is an executable file and runs `bb --version`. Returns the path and version.
+fn check_binary(bb_path: Option<String>) -> Result<(String, String), FfiError> {
+    let bb_path = match bb_path {
+        Some(p) => p,
+        None => find_bb_binary()?,
+    };
+    // A bare name, e.g. from BB_BINARY_PATH, is looked up on PATH.
+    let path = if bb_path.contains(std::path::MAIN_SEPARATOR) {
+        std::path::PathBuf::from(&bb_path)
+    } else {
+        which::which(&bb_path).map_err(|e| FfiError::backend(format!("bb binary {:?} not found on PATH: {}", bb_path, e)))?
+    };
 
-    // Check ~/.bb/bb (old installer)
-    let bb_path = format!("{}/.bb/bb", home);
-    if std::path::Path::new(&bb_path).exists() {
-        return bb_path;
+    let metadata = std::fs::metadata(&path)
+        .map_err(|e| FfiError::backend(format!("bb binary {} not accessible: {}", path.display(), e)))?;
+    if !metadata.is_file() {
+        return Err(FfiError::backend(format!("bb binary {} is not a file", path.display())));
     }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(FfiError::backend(format!("bb binary {} is not executable", path.display())));
+        }
+    }
+
+    let path = path.to_string_lossy().into_owned();
+    let version = bb_binary_version(&path)?;
+    Ok((path, version))
+}
+
+#[derive(Serialize)]
+struct BinaryCheck {
+    path: String,
+    version: String,
+}
+
+/// Runs the `bb` binary lookup used by the pipe backend, without an explicitly
+/// configured path, and checks the result: returns JSON `{"path", "version"}`
+/// or an error saying what is wrong, e.g. every path tried.
+#[no_mangle]
+pub extern "C" fn bb_check_binary() -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let (path, version) = check_binary(None)?;
+            serde_json::to_vec(&BinaryCheck { path, version }).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
 
-    // Default to 'bb' and hope for the best
-    "bb".to_string()
+/// Directory holding the SRS (CRS) points, set by `bb_set_srs_path`; `None`
+/// falls back to `BB_CRS_PATH`, then to the backend's own default.
+static SRS_PATH: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+/// The configured SRS directory and where it came from (`"set"` or `"env"`),
+/// or `None` when the backend default applies.
+fn srs_path() -> Option<(String, &'static str)> {
+    if let Some(path) = SRS_PATH.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+        return Some((path, "set"));
+    }
+    std::env::var("BB_CRS_PATH").ok().filter(|p| !p.is_empty()).map(|p| (p, "env"))
 }
 
-fn get_api() -> Result<std::sync::MutexGuard<'static, ApiEnum>, String> {
-    let api_mutex = BB_API.get_or_init(|| {
-        let backend_type = std::env::var("BB_BACKEND_TYPE").unwrap_or_else(|_| "native".to_string());
-        
-        let api = if backend_type.to_lowercase() == "native" {
-            #[cfg(feature = "native-backend")]
-            {
-                let backend = FfiBackend::new().expect("Failed to create FfiBackend");
-                ApiEnum::Native(BarretenbergApi::new(backend))
+/// Sets the directory the backend loads (and downloads) the SRS from. An
+/// empty string or null restores `BB_CRS_PATH`, then the backend default.
+/// Affects pipe backends started afterwards, so call it before the first
+/// proof; the native backend only reads `CRS_PATH` from the environment.
+#[no_mangle]
+pub extern "C" fn bb_set_srs_path(path: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let path = if path.is_null() { String::new() } else { unsafe { cstr_to_string(path) }? };
+            if std::path::Path::new(&path).is_file() {
+                return Err(FfiError::invalid_argument(format!("SRS path {} is a file, expected a directory", path)));
             }
-            #[cfg(not(feature = "native-backend"))]
-            {
-                let bb_path = find_bb_binary();
-                let backend = PipeBackend::new(&bb_path, Some(16)).expect("Failed to create PipeBackend");
-                ApiEnum::Pipe(BarretenbergApi::new(backend))
+            *SRS_PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(path).filter(|p| !p.is_empty());
+            Ok(())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct SrsStatus {
+    path: Option<String>,
+    source: &'static str,
+    loaded: bool,
+    points: u32,
+}
+
+/// Reports the SRS configuration as JSON `{"path", "source", "loaded",
+/// "points"}`. `source` is `"set"`, `"env"` or `"default"` (`path` is null
+/// then); `points` is the largest size loaded with
+/// `bb_init_srs_from_bytecode` or `bb_ensure_srs` by the current backend, 0
+/// if none. Does not
+/// start a backend.
+#[no_mangle]
+pub extern "C" fn bb_srs_status() -> BBResult {
+    ffi_boundary(|| {
+        let (path, source) = match srs_path() {
+            Some((path, source)) => (Some(path), source),
+            None => (None, "default"),
+        };
+        let points = lock_backend().as_ref().map_or(0, |b| b.srs_points);
+        match serde_json::to_vec(&SrsStatus { path, source, loaded: points > 0, points }) {
+            Ok(v) => ok(v),
+            Err(e) => err(FfiError::serialization(e)),
+        }
+    })
+}
+
+/// Thread count of backends configured without one: `BB_THREADS` if set, so
+/// it can be fixed independently of the machine, else the CPU count.
+fn default_threads() -> usize {
+    if let Some(threads) = std::env::var("BB_THREADS").ok().and_then(|t| t.parse().ok()).filter(|t| *t > 0) {
+        return threads;
+    }
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Whether the `bb` subprocess is pinned to as many CPUs as it has threads.
+static THREAD_AFFINITY: AtomicBool = AtomicBool::new(false);
+
+/// Pins the pipe backend's `bb` subprocess to CPUs `0..threads`, for
+/// reproducible benchmarks (off by default). Applies to backends started
+/// afterwards; `bb_reset_backend` restarts the current one. Only implemented
+/// on Linux. The native backend runs in the caller's process and isn't
+/// pinned; its thread count is fixed through the configured threads or
+/// `BB_THREADS`.
+#[no_mangle]
+pub extern "C" fn bb_set_thread_affinity(enabled: bool) {
+    THREAD_AFFINITY.store(enabled, Ordering::Relaxed);
+}
+
+#[cfg(not(unix))]
+const PIPE_UNSUPPORTED: &str = "The pipe backend needs a Unix platform; use the native backend";
+
+/// Creates a backend; the pid is that of the pipe backend's `bb` subprocess.
+/// `threads` is passed to that subprocess; the native library sizes its pool
+/// from `HARDWARE_CONCURRENCY` in the process environment instead, which is
+/// left to the host.
+/// `bb`'s stderr goes to `output`.
+fn create_api(
+    backend_type: &str,
+    bb_path: Option<String>,
+    threads: usize,
+    output: &Arc<logging::Output>,
+) -> Result<(ApiEnum, Option<u32>), FfiError> {
+    let crs_path = srs_path().map(|(path, _)| path);
+
+    #[cfg(feature = "native-backend")]
+    if backend_type.to_lowercase() == "native" {
+        // The native library reads `CRS_PATH` from the process environment,
+        // which isn't ours to change while the host runs.
+        if crs_path.is_some() {
+            logging::log(
+                logging::LogLevel::Warn,
+                "The SRS path only applies to the pipe backend; set CRS_PATH before starting for the native one",
+            );
+        }
+        match FfiBackend::new() {
+            Ok(backend) => return Ok((ApiEnum::Native(BarretenbergApi::new(backend)), None)),
+            // Opt-in: carry on below with the pipe backend.
+            Err(e) if std::env::var("BB_BACKEND_FALLBACK").is_ok_and(|v| v.eq_ignore_ascii_case("pipe")) => {
+                logging::log(
+                    logging::LogLevel::Warn,
+                    format!("Failed to create FfiBackend: {}, falling back to the pipe backend", e),
+                );
             }
-        } else {
-            let bb_path = find_bb_binary();
-            let backend = PipeBackend::new(&bb_path, Some(16)).expect("Failed to create PipeBackend");
-            ApiEnum::Pipe(BarretenbergApi::new(backend))
+            Err(e) => return Err(FfiError::backend(format!("Failed to create FfiBackend: {}", e))),
+        }
+    }
+    #[cfg(not(feature = "native-backend"))]
+    let _ = backend_type;
+
+    #[cfg(unix)]
+    {
+        let bb_path = match bb_path {
+            Some(p) => p,
+            None => find_bb_binary()?,
         };
-        
-        std::sync::Mutex::new(api)
-    });
-    
-    api_mutex.lock().map_err(|e| format!("Mutex lock failed: {}", e))
+        let backend = spawn_pipe_backend(&bb_path, threads, crs_path.as_deref(), output)?;
+        let pid = backend.pid();
+        Ok((ApiEnum::Pipe(BarretenbergApi::new(backend)), Some(pid)))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (bb_path, threads, crs_path, output);
+        Err(FfiError::unsupported(PIPE_UNSUPPORTED))
+    }
+}
+
+/// Attempts at spawning the `bb` subprocess before giving up.
+static SPAWN_ATTEMPTS: AtomicU32 = AtomicU32::new(3);
+/// Delay before the first spawn retry in milliseconds, doubled for each
+/// further retry.
+static SPAWN_RETRY_DELAY_MS: AtomicU32 = AtomicU32::new(100);
+
+/// Configures how often spawning the `bb` subprocess is retried, e.g. on a
+/// transient resource shortage: up to `attempts` tries in total (0 is treated
+/// as 1), waiting `delay_ms` before the first retry and twice as long before
+/// each following one. Defaults to 3 attempts and 100 ms.
+#[no_mangle]
+pub extern "C" fn bb_set_spawn_retry(attempts: u32, delay_ms: u32) {
+    SPAWN_ATTEMPTS.store(attempts.max(1), Ordering::Relaxed);
+    SPAWN_RETRY_DELAY_MS.store(delay_ms, Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+fn spawn_pipe_backend(
+    bb_path: &str,
+    threads: usize,
+    crs_path: Option<&str>,
+    output: &Arc<logging::Output>,
+) -> Result<pipe::BbProcess, FfiError> {
+    let attempts = SPAWN_ATTEMPTS.load(Ordering::Relaxed).max(1);
+    let mut delay = Duration::from_millis(SPAWN_RETRY_DELAY_MS.load(Ordering::Relaxed) as u64);
+    let mut attempt = 1;
+    loop {
+        match pipe::BbProcess::spawn(bb_path, threads, crs_path, output.clone()) {
+            Ok(backend) => return Ok(backend),
+            Err(e) if attempt < attempts => {
+                logging::log(
+                    logging::LogLevel::Warn,
+                    format!("Failed to spawn bb (attempt {}/{}): {}, retrying in {:?}", attempt, attempts, e, delay),
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(FfiError::backend(format!(
+                    "Failed to spawn bb after {} attempt(s): {}",
+                    attempts, e
+                )))
+            }
+        }
+    }
+}
+
+/// Locks the backend, initializing it from `BB_BACKEND_TYPE` on first use and
+/// rebuilding it with its configuration if it was poisoned.
+fn get_api() -> Result<BackendGuard, FfiError> {
+    let mut slot = lock_backend();
+    match slot.as_mut() {
+        Some(backend) if backend.poisoned => {
+            logging::log(logging::LogLevel::Warn, "Rebuilding poisoned backend");
+            backend.reconnect()?
+        }
+        Some(_) => {}
+        // Only the global backend starts on first use; a handle's slot is
+        // empty once it was freed.
+        None if !std::ptr::eq(current_slot(), &BB_API) => {
+            return Err(FfiError::invalid_argument("Backend handle was freed"));
+        }
+        None => {
+            let config = BackendConfig {
+                backend_type: std::env::var("BB_BACKEND_TYPE").unwrap_or_else(|_| "native".to_string()),
+                bb_path: None,
+                threads: None,
+            };
+            *slot = Some(Backend::new(config)?);
+        }
+    }
+    Ok(BackendGuard(slot))
+}
+
+/// Version of the C ABI exposed by this library. Bumped on every breaking
+/// change to the exported structs or function signatures.
+pub const BB_FFI_ABI_VERSION: u32 = 2;
+
+/// Error codes reported in `BBResult.err_code`.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// No error.
+    Ok = 0,
+    /// Null pointer, invalid UTF-8, or malformed JSON / witness argument.
+    InvalidArgument = 1,
+    /// Bytecode or proof buffer could not be decoded.
+    Decode = 2,
+    /// Backend failed to initialize or returned an error.
+    Backend = 3,
+    /// A response could not be serialized.
+    Serialization = 4,
+    /// A backend call exceeded the configured timeout.
+    Timeout = 5,
+    /// The polled job has not finished yet.
+    Pending = 6,
+    /// The operation was cancelled, or cannot be.
+    Cancelled = 7,
+    /// The operation is not supported by this build or backend.
+    Unsupported = 8,
+    /// A proof was made by a different backend version than the verifier's.
+    VersionMismatch = 9,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+struct FfiError {
+    code: ErrorCode,
+    message: String,
+    /// Machine-readable details, e.g. the failing witness index; only
+    /// reported in the JSON error format.
+    context: serde_json::Map<String, serde_json::Value>,
+}
+
+impl FfiError {
+    fn new(code: ErrorCode, message: impl std::fmt::Display) -> Self {
+        FfiError { code, message: message.to_string(), context: serde_json::Map::new() }
+    }
+
+    fn with_context(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.context.insert(key.to_string(), value.into());
+        self
+    }
+
+    fn invalid_argument(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::InvalidArgument, message)
+    }
+
+    fn decode(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Decode, message)
+    }
+
+    fn backend(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Backend, message)
+    }
+
+    fn serialization(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Serialization, message)
+    }
+
+    fn timeout(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Timeout, message)
+    }
+
+    fn pending(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Pending, message)
+    }
+
+    fn cancelled(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Cancelled, message)
+    }
+
+    fn unsupported(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Unsupported, message)
+    }
+
+    fn version_mismatch(message: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::VersionMismatch, message)
+    }
 }
 
 #[repr(C)]
@@ -85,26 +717,62 @@ pub struct ByteBuffer {
 #[repr(C)]
 pub struct BBResult {
     pub ok: bool,
+    pub err_code: i32,
     pub err: *mut c_char,
     pub data: ByteBuffer,
 }
 
-fn ok(mut data: Vec<u8>) -> BBResult {
+/// Marks allocations made by `ok`, so `bb_free_bytes` can refuse buffers it
+/// didn't hand out.
+const BUFFER_MAGIC: u64 = 0xbb5f_b0ff_0000_0001;
+
+/// Bytes in front of every returned buffer: `BUFFER_MAGIC` and the data
+/// length, both native-endian u64.
+const BUFFER_HEADER: usize = 16;
+
+fn ok(data: Vec<u8>) -> BBResult {
     let len = data.len();
-    let cap = data.capacity();
-    let ptr = data.as_mut_ptr();
-    std::mem::forget(data);
+    let mut alloc = Vec::with_capacity(BUFFER_HEADER + len);
+    alloc.extend_from_slice(&BUFFER_MAGIC.to_ne_bytes());
+    alloc.extend_from_slice(&(len as u64).to_ne_bytes());
+    alloc.extend_from_slice(&data);
+    drop(data);
+
+    let cap = alloc.capacity();
+    let ptr = unsafe { alloc.as_mut_ptr().add(BUFFER_HEADER) };
+    std::mem::forget(alloc);
     BBResult {
         ok: true,
+        err_code: ErrorCode::Ok as i32,
         err: null_mut(),
         data: ByteBuffer { ptr, len, cap },
     }
 }
 
-fn err(msg: String) -> BBResult {
-    let c = CString::new(msg).unwrap_or_else(|_| CString::new("Unknown error").unwrap());
+/// Whether `BBResult.err` holds a JSON object rather than the bare message.
+static ERROR_FORMAT_JSON: AtomicBool = AtomicBool::new(false);
+
+/// Selects the format of `BBResult.err`: the bare message (the default) or,
+/// with `json`, an object `{"code", "message", "context"}` where `context`
+/// holds details such as `"witness_index"` or, for backend errors, the
+/// `"backend_output"` of the pipe backend's `bb` subprocess.
+#[no_mangle]
+pub extern "C" fn bb_set_error_format(json: bool) {
+    ERROR_FORMAT_JSON.store(json, Ordering::Relaxed);
+}
+
+fn err(e: FfiError) -> BBResult {
+    let message = if ERROR_FORMAT_JSON.load(Ordering::Relaxed) {
+        serde_json::json!({ "code": e.code as i32, "message": e.message, "context": e.context }).to_string()
+    } else {
+        e.message
+    };
+    // Escape nul bytes, e.g. from binary backend output, rather than losing
+    // the message.
+    let c = CString::new(message.replace('\0', "\\0")).unwrap_or_default();
     BBResult {
         ok: false,
+        err_code: e.code as i32,
         err: c.into_raw(),
         data: ByteBuffer {
             ptr: null_mut(),
@@ -114,259 +782,4695 @@ fn err(msg: String) -> BBResult {
     }
 }
 
+/// Runs `f`, turning a panic into a `Backend` error: unwinding out of an
+/// `extern "C"` function is undefined behavior, and a panic while the backend
+/// was locked already gets it rebuilt on the next call.
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, FfiError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).map_err(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        logging::log(logging::LogLevel::Error, format!("Internal panic: {}", msg));
+        FfiError::backend(format!("Internal panic: {}", msg))
+    })
+}
+
+/// Body of every `extern "C"` function returning a `BBResult`, so a panic
+/// reaches the caller as an error instead of unwinding across the boundary.
+/// Trivial setters that only store an atomic are left unwrapped.
+fn ffi_boundary(f: impl FnOnce() -> BBResult) -> BBResult {
+    let _scope = CallScope::enter();
+    catch_panic(f).unwrap_or_else(err)
+}
+
+/// `ffi_boundary` for functions without a `BBResult`, returning `default`
+/// after a panic; the panic message goes to the log callback.
+fn ffi_boundary_or<T>(default: T, f: impl FnOnce() -> T) -> T {
+    let _scope = CallScope::enter();
+    catch_panic(f).unwrap_or(default)
+}
+
+#[no_mangle]
+pub extern "C" fn bb_abi_version() -> u32 {
+    BB_FFI_ABI_VERSION
+}
+
+/// Frees a buffer returned in a `BBResult`. A buffer without the header `ok`
+/// writes, or whose fields were altered or that was already freed, is
+/// logged and leaked rather than freed, since freeing it would corrupt the
+/// heap.
 #[no_mangle]
 pub extern "C" fn bb_free_bytes(buf: ByteBuffer) {
-    if !buf.ptr.is_null() {
+    ffi_boundary_or((), || {
+        if buf.ptr.is_null() {
+            return;
+        }
         unsafe {
-            drop(Vec::from_raw_parts(buf.ptr, buf.len, buf.cap));
+            let base = buf.ptr.sub(BUFFER_HEADER);
+            let magic = u64::from_ne_bytes(*(base as *const [u8; 8]));
+            let len = u64::from_ne_bytes(*(base.add(8) as *const [u8; 8]));
+            if magic != BUFFER_MAGIC || len != buf.len as u64 || buf.cap < BUFFER_HEADER + buf.len {
+                logging::log(
+                    logging::LogLevel::Error,
+                    format!("bb_free_bytes: {:p} is not a live buffer from this library, not freeing it", buf.ptr),
+                );
+                return;
+            }
+            // Catches a second free of the same buffer while its memory
+            // hasn't been reused.
+            base.write_bytes(0, 8);
+            drop(Vec::from_raw_parts(base, BUFFER_HEADER + buf.len, buf.cap));
         }
-    }
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn bb_free_err(s: *mut c_char) {
-    if !s.is_null() {
-        unsafe {
-            drop(CString::from_raw(s));
+    ffi_boundary_or((), || {
+        if !s.is_null() {
+            unsafe {
+                drop(CString::from_raw(s));
+            }
         }
-    }
+    })
 }
 
-unsafe fn cstr_to_string(p: *const c_char) -> Result<String, String> {
+unsafe fn cstr_to_string(p: *const c_char) -> Result<String, FfiError> {
     if p.is_null() {
-        return Err("null pointer".into());
+        return Err(FfiError::invalid_argument("null pointer"));
     }
     CStr::from_ptr(p)
         .to_str()
         .map(|s| s.to_owned())
-        .map_err(|e| e.to_string())
+        .map_err(FfiError::invalid_argument)
 }
 
-fn decode_bytecode(bytecode_b64_gz: &str) -> Result<Vec<u8>, String> {
-    let compressed = general_purpose::STANDARD
-        .decode(bytecode_b64_gz)
-        .map_err(|e| e.to_string())?;
-    let mut decoder = GzDecoder::new(&compressed[..]);
-    let mut decompressed = Vec::new();
-    decoder.read_to_end(&mut decompressed).map_err(|e| e.to_string())?;
-    Ok(decompressed)
+unsafe fn byte_slice<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::invalid_argument("null pointer"));
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
 }
 
-#[no_mangle]
-pub extern "C" fn bb_init_srs_from_bytecode(_bytecode_b64_gz: *const c_char) -> BBResult {
-    ok(vec![])
-}
+const ORACLE_HASH_TYPES: [&str; 3] = ["poseidon2", "keccak", "blake2s"];
 
-#[derive(Deserialize)]
-struct WitnessJson {
-    witness: Vec<String>,
+/// Parses settings and canonicalizes the oracle hash name, so that prove,
+/// compute-vk and verify all see the same flavor for the same logical setting.
+///
+/// Besides the `ProofSystemSettings` fields, the JSON may carry an optional
+/// `"threads"` count, which the backend commands of the call run with. On the
+/// pipe backend a count other than the configured one restarts `bb` (and
+/// reloads its SRS) before the call and again after it.
+/// Settings after filling in defaults, with what was notable on the way.
+struct ResolvedSettings {
+    settings: ProofSystemSettings,
+    /// Per-call thread count from the `"threads"` extension key.
+    threads: Option<usize>,
+    /// Name reported to the backend from the `"circuit_name"` extension key.
+    circuit_name: Option<String>,
+    /// Keys that were missing and took their default value.
+    defaulted: Vec<String>,
+    /// Keys that aren't recognized and were ignored.
+    unknown: Vec<String>,
 }
 
-fn parse_field(s: &str) -> Result<[u8; 32], String> {
-    let bytes = if s.starts_with("0x") {
-        let hex_str = &s[2..];
-        let mut decoded = vec![0u8; 32];
-        let h = hex::decode(hex_str).map_err(|e| e.to_string())?;
-        if h.len() > 32 {
-            return Err("Hex string too long for field element".into());
-        }
-        let offset = 32 - h.len();
-        decoded[offset..].copy_from_slice(&h);
-        decoded
-    } else {
-        let val = s.parse::<u128>().map_err(|e| e.to_string())?;
-        let mut decoded = [0u8; 32];
-        let b = val.to_be_bytes();
-        decoded[32-16..].copy_from_slice(&b);
-        decoded.to_vec()
+/// Resolves a settings JSON object without side effects. Besides the fields
+/// of `ProofSystemSettings`, `"threads"`, `"zk"` and `"circuit_name"` are
+/// understood; missing
+/// fields take their values from `default_settings()`.
+fn resolve_settings(settings_str: &str) -> Result<ResolvedSettings, FfiError> {
+    let value: serde_json::Value = serde_json::from_str(settings_str).map_err(FfiError::invalid_argument)?;
+    let serde_json::Value::Object(mut obj) = value else {
+        return Err(FfiError::invalid_argument("Settings must be a JSON object"));
     };
-    
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&bytes);
-    Ok(arr)
-}
 
-#[derive(Serialize)]
-struct WitnessMapWrapper(BTreeMap<u32, serde_bytes::ByteBuf>);
+    let threads = match obj.remove("threads") {
+        Some(threads) => Some(
+            threads
+                .as_u64()
+                .filter(|t| *t > 0)
+                .ok_or_else(|| FfiError::invalid_argument("\"threads\" must be a positive integer"))? as usize,
+        ),
+        None => None,
+    };
 
-#[derive(Serialize)]
-struct StackItemWrapper(u32, WitnessMapWrapper);
+    let circuit_name = match obj.remove("circuit_name") {
+        Some(name) => Some(
+            name.as_str()
+                .filter(|n| !n.is_empty())
+                .ok_or_else(|| FfiError::invalid_argument("\"circuit_name\" must be a non-empty string"))?
+                .to_string(),
+        ),
+        None => None,
+    };
 
-fn call_bb(cmd: Command) -> Result<barretenberg_rs::generated_types::Response, String> {
-    let mut api_guard = get_api()?;
-    
-    match &mut *api_guard {
-        ApiEnum::Pipe(api) => {
-            match cmd {
-                Command::CircuitComputeVk(data) => {
-                    api.circuit_compute_vk(data.circuit, data.settings)
-                        .map(barretenberg_rs::generated_types::Response::CircuitComputeVkResponse)
-                        .map_err(|e| e.to_string())
-                }
-                Command::CircuitProve(data) => {
-                    api.circuit_prove(data.circuit, &data.witness, data.settings)
-                        .map(barretenberg_rs::generated_types::Response::CircuitProveResponse)
-                        .map_err(|e| e.to_string())
-                }
-                Command::CircuitVerify(data) => {
-                    api.circuit_verify(&data.verification_key, data.public_inputs, data.proof, data.settings)
-                        .map(barretenberg_rs::generated_types::Response::CircuitVerifyResponse)
-                        .map_err(|e| e.to_string())
-                }
-                _ => Err("Unsupported command".to_string())
-            }
-        }
-        #[cfg(feature = "native-backend")]
-        ApiEnum::Native(api) => {
-            match cmd {
-                Command::CircuitComputeVk(data) => {
-                    api.circuit_compute_vk(data.circuit, data.settings)
-                        .map(barretenberg_rs::generated_types::Response::CircuitComputeVkResponse)
-                        .map_err(|e| e.to_string())
-                }
-                Command::CircuitProve(data) => {
-                    api.circuit_prove(data.circuit, &data.witness, data.settings)
-                        .map(barretenberg_rs::generated_types::Response::CircuitProveResponse)
-                        .map_err(|e| e.to_string())
-                }
-                Command::CircuitVerify(data) => {
-                    api.circuit_verify(&data.verification_key, data.public_inputs, data.proof, data.settings)
-                        .map(barretenberg_rs::generated_types::Response::CircuitVerifyResponse)
-                        .map_err(|e| e.to_string())
-                }
-                _ => Err("Unsupported command".to_string())
-            }
+    // `"zk": bool` is accepted as the positive spelling of `disable_zk`.
+    if let Some(zk) = obj.remove("zk") {
+        let zk = zk.as_bool().ok_or_else(|| FfiError::invalid_argument("\"zk\" must be a boolean"))?;
+        if obj.get("disable_zk").and_then(serde_json::Value::as_bool) == Some(zk) {
+            return Err(FfiError::invalid_argument("\"zk\" contradicts \"disable_zk\""));
         }
+        obj.insert("disable_zk".to_string(), serde_json::Value::Bool(!zk));
     }
-}
-
-#[no_mangle]
-pub extern "C" fn bb_prove_ultrahonk(
-    bytecode_b64_gz: *const c_char,
-    witness_json: *const c_char,
-    settings_json: *const c_char,
-) -> BBResult {
-    let res: Result<Vec<u8>, String> = (|| {
-        let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
-        let bytecode = decode_bytecode(&bytecode_str)?;
-        
-        let wj_str = unsafe { cstr_to_string(witness_json) }?;
-        let parsed: WitnessJson = serde_json::from_str(&wj_str).map_err(|e| e.to_string())?;
-
-        let settings_str = unsafe { cstr_to_string(settings_json) }?;
-        let settings: ProofSystemSettings = serde_json::from_str(&settings_str).map_err(|e| e.to_string())?;
 
-        let mut witness_map = BTreeMap::new();
-        for (i, val_str) in parsed.witness.into_iter().enumerate() {
-            let field_bytes = parse_field(&val_str)?;
-            witness_map.insert(i as u32, serde_bytes::ByteBuf::from(field_bytes.to_vec()));
+    let serde_json::Value::Object(defaults) = serde_json::to_value(default_settings()).map_err(FfiError::serialization)? else {
+        return Err(FfiError::serialization("Default settings are not a JSON object"));
+    };
+    let unknown: Vec<String> = obj.keys().filter(|k| !defaults.contains_key(*k)).cloned().collect();
+    let mut defaulted = Vec::new();
+    for (key, default) in defaults {
+        if !obj.contains_key(&key) {
+            defaulted.push(key.clone());
+            obj.insert(key, default);
         }
+    }
 
-        let stack_item = StackItemWrapper(0, WitnessMapWrapper(witness_map));
-        
-        #[derive(Serialize)]
-        struct FinalWitnessStack {
-            stack: Vec<StackItemWrapper>,
-        }
-        let final_stack = FinalWitnessStack { stack: vec![stack_item] };
+    let mut settings: ProofSystemSettings =
+        serde_json::from_value(serde_json::Value::Object(obj)).map_err(FfiError::invalid_argument)?;
 
-        let encoded = rmp_serde::to_vec(&final_stack)
-            .map_err(|e| format!("Failed to serialize witness stack: {}", e))?;
-        let mut witness_bytes = vec![2u8]; 
-        witness_bytes.extend(encoded);
+    settings.oracle_hash_type = settings.oracle_hash_type.to_lowercase();
+    if !ORACLE_HASH_TYPES.contains(&settings.oracle_hash_type.as_str()) {
+        return Err(FfiError::invalid_argument(format!(
+            "Unknown oracle hash type {:?}, expected one of {:?}",
+            settings.oracle_hash_type, ORACLE_HASH_TYPES
+        )));
+    }
+    Ok(ResolvedSettings { settings, threads, circuit_name, defaulted, unknown })
+}
+
+/// Reads the settings argument of a call. A null or empty argument stands for
+/// the settings from the environment, see `env_settings_json`.
+unsafe fn settings_arg(settings_json: *const c_char) -> Result<String, FfiError> {
+    if settings_json.is_null() {
+        return env_settings_json();
+    }
+    let settings_str = cstr_to_string(settings_json)?;
+    if settings_str.trim().is_empty() {
+        return env_settings_json();
+    }
+    Ok(settings_str)
+}
+
+/// Settings JSON built from `BB_ORACLE_HASH`, `BB_ZK` and
+/// `BB_IPA_ACCUMULATION`; unset variables leave the library default. Explicit
+/// settings JSON never looks at these.
+fn env_settings_json() -> Result<String, FfiError> {
+    let env_bool = |name: &str| -> Result<Option<bool>, FfiError> {
+        match std::env::var(name) {
+            Err(_) => Ok(None),
+            Ok(v) => match v.trim().to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Ok(Some(true)),
+                "0" | "false" | "no" | "off" => Ok(Some(false)),
+                _ => Err(FfiError::invalid_argument(format!("{} must be a boolean, got {:?}", name, v))),
+            },
+        }
+    };
+
+    let mut obj = serde_json::Map::new();
+    if let Ok(oracle_hash) = std::env::var("BB_ORACLE_HASH") {
+        obj.insert("oracle_hash_type".to_string(), serde_json::Value::String(oracle_hash));
+    }
+    if let Some(zk) = env_bool("BB_ZK")? {
+        obj.insert("zk".to_string(), serde_json::Value::Bool(zk));
+    }
+    if let Some(ipa) = env_bool("BB_IPA_ACCUMULATION")? {
+        obj.insert("ipa_accumulation".to_string(), serde_json::Value::Bool(ipa));
+    }
+    Ok(serde_json::Value::Object(obj).to_string())
+}
+
+/// Parses the settings argument of a backend call and applies them.
+unsafe fn parse_settings(settings_json: *const c_char) -> Result<ProofSystemSettings, FfiError> {
+    let settings_str = settings_arg(settings_json)?;
+    apply_settings(resolve_settings(&settings_str)?)
+}
+
+/// Applies the side effects of resolved settings: the `"threads"` override
+/// and circuit name become the options of the call running on this thread.
+fn apply_settings(resolved: ResolvedSettings) -> Result<ProofSystemSettings, FfiError> {
+    CALL_OPTIONS.with(|o| {
+        *o.borrow_mut() = CallOptions { circuit_name: resolved.circuit_name, threads: resolved.threads };
+    });
+    Ok(resolved.settings)
+}
+
+const DEFAULT_MAX_BYTECODE_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Upper bound on the decompressed size of a gzipped input.
+static MAX_BYTECODE_SIZE: AtomicU64 = AtomicU64::new(DEFAULT_MAX_BYTECODE_SIZE);
+
+/// Sets the maximum decompressed size of gzipped bytecode and witnesses, in
+/// bytes; 0 restores the default of 512 MiB. Larger inputs are rejected with
+/// a `Decode` error as soon as the limit is crossed, before they are fully
+/// inflated.
+#[no_mangle]
+pub extern "C" fn bb_set_max_bytecode_size(max_bytes: u64) {
+    let max_bytes = if max_bytes == 0 { DEFAULT_MAX_BYTECODE_SIZE } else { max_bytes };
+    MAX_BYTECODE_SIZE.store(max_bytes, Ordering::Relaxed);
+}
+
+fn decode_bytecode(bytecode_b64_gz: &str) -> Result<Vec<u8>, FfiError> {
+    decode_b64_gz(bytecode_b64_gz, "bytecode")
+}
+
+/// Decodes base64 then gunzips, keeping the two failures apart; `what` names
+/// the input in base64 errors.
+fn decode_b64_gz(input: &str, what: &str) -> Result<Vec<u8>, FfiError> {
+    let compressed = decode_base64(input).map_err(|e| FfiError::decode(format!("invalid base64 in {}: {}", what, e)))?;
+    gunzip_bounded(&compressed)
+}
+
+/// Decodes standard base64 or, as a fallback for transports that use it,
+/// URL-safe base64, padded or not. The alphabet and length are checked
+/// before decoding, so errors point at the offending character.
+fn decode_base64(input: &str) -> Result<Vec<u8>, String> {
+    let data = input.trim_end_matches('=');
+    if data.is_empty() {
+        return Err("empty input".to_string());
+    }
+    if input.len() - data.len() > 2 {
+        return Err("too much padding".to_string());
+    }
+    let (mut standard, mut url_safe) = (false, false);
+    for (i, c) in data.char_indices() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' => {}
+            '+' | '/' => standard = true,
+            '-' | '_' => url_safe = true,
+            _ => return Err(format!("unexpected character {:?} at offset {}", c, i)),
+        }
+    }
+    if standard && url_safe {
+        return Err("mixes the standard and URL-safe alphabets".to_string());
+    }
+    if data.len() % 4 == 1 {
+        return Err(format!("length {} is not a valid base64 length", input.len()));
+    }
+
+    let engine = if url_safe { &general_purpose::URL_SAFE_NO_PAD } else { &general_purpose::STANDARD_NO_PAD };
+    engine.decode(data).map_err(|e| e.to_string())
+}
+
+/// Inflates `compressed` chunk by chunk, failing once the output would exceed
+/// `MAX_BYTECODE_SIZE`.
+fn gunzip_bounded(compressed: &[u8]) -> Result<Vec<u8>, FfiError> {
+    let max_size = MAX_BYTECODE_SIZE.load(Ordering::Relaxed);
+    let mut decoder = GzDecoder::new(compressed);
+    let mut decompressed = Vec::new();
+    let mut chunk = [0u8; 64 * 1024];
+    loop {
+        let n = match decoder.read(&mut chunk) {
+            Ok(0) => return Ok(decompressed),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(FfiError::decode(format!("gzip decompression failed: {}", e))),
+        };
+        if (decompressed.len() + n) as u64 > max_size {
+            return Err(FfiError::decode(format!("Decompressed input exceeds the {} byte limit", max_size)));
+        }
+        decompressed.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// Selects and initializes the backend explicitly, instead of relying on the
+/// `BB_BACKEND_TYPE` environment variable. Must be called before any other
+/// backend call; fails if the backend has already been initialized.
+/// `bb_binary_path` may be null to use the default lookup, and `threads == 0`
+/// uses the number of available CPUs. Both only apply to the pipe backend; the
+/// native one takes its thread count from `HARDWARE_CONCURRENCY`.
+#[no_mangle]
+pub extern "C" fn bb_configure_backend(
+    backend_type: *const c_char,
+    bb_binary_path: *const c_char,
+    threads: u32,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let backend_type = unsafe { cstr_to_string(backend_type) }?.to_lowercase();
+            let bb_path = if bb_binary_path.is_null() {
+                None
+            } else {
+                Some(unsafe { cstr_to_string(bb_binary_path) }?)
+            };
+            let threads = if threads == 0 { None } else { Some(threads as usize) };
+            let config = BackendConfig { backend_type, bb_path, threads };
+            check_backend_config(&config)?;
+
+            let mut slot = lock_backend();
+            if slot.is_some() {
+                return Err(FfiError::invalid_argument("Backend already initialized"));
+            }
+            *slot = Some(Backend::new(config)?);
+            Ok(())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Rejects a backend configuration that cannot work, before spawning it.
+fn check_backend_config(config: &BackendConfig) -> Result<(), FfiError> {
+    match config.backend_type.as_str() {
+        // Fail here rather than with a spawn error on the first proof.
+        "pipe" => {
+            #[cfg(not(unix))]
+            return Err(FfiError::unsupported(PIPE_UNSUPPORTED));
+            #[cfg(unix)]
+            check_binary(config.bb_path.clone())?;
+        }
+        "native" => {
+            #[cfg(not(feature = "native-backend"))]
+            return Err(FfiError::invalid_argument("Native backend not available: built without the native-backend feature"));
+        }
+        other => return Err(FfiError::invalid_argument(format!("Unknown backend type: {}", other))),
+    }
+    Ok(())
+}
+
+fn default_settings() -> ProofSystemSettings {
+    ProofSystemSettings {
+        ipa_accumulation: false,
+        oracle_hash_type: "poseidon2".to_string(),
+        disable_zk: false,
+        optimized_solidity_verifier: false,
+    }
+}
+
+/// Tears down the current backend and spawns a fresh one with the same
+/// configuration, e.g. after the `bb` subprocess died. Initializes the backend
+/// if it wasn't yet.
+#[no_mangle]
+pub extern "C" fn bb_reset_backend() -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let mut slot = lock_backend();
+            if let Some(backend) = slot.as_mut() {
+                let threads = backend.config.threads.unwrap_or_else(default_threads);
+                return backend.respawn(threads);
+            }
+            drop(slot);
+            get_api().map(|_| ())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Shuts the global backend down: the `bb` subprocess of the pipe backend is
+/// asked to exit and killed if it doesn't within a few seconds, and the native
+/// backend's resources are released. Waits for a call in progress to finish
+/// first. Any later call initializes a backend again, from `BB_BACKEND_TYPE`
+/// rather than an earlier `bb_configure_backend`.
+#[no_mangle]
+pub extern "C" fn bb_shutdown() -> BBResult {
+    ffi_boundary(|| {
+        let backend = lock_backend().take();
+        if let Some(backend) = backend {
+            backend.shutdown();
+            logging::log(logging::LogLevel::Debug, "Backend shut down");
+        }
+        ok(vec![])
+    })
+}
+
+/// Warms up the SRS for the given circuit so the first proof doesn't pay the
+/// load/download cost. The circuit size is queried from the backend and a VK
+/// is computed, which forces the backend to load the SRS up to the circuit's
+/// dyadic size. Returns that size as a little-endian u32.
+#[no_mangle]
+pub extern "C" fn bb_init_srs_from_bytecode(bytecode_b64_gz: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let settings = default_settings();
+
+            let info = circuit_stats(bytecode.clone(), settings.clone())?;
+            compute_vk(bytecode, settings)
+                .map_err(|e| FfiError::backend(format!("Failed to load SRS of size {}: {}", info.num_gates_dyadic, e)))?;
+
+            let srs_size: u32 = info.num_gates_dyadic as _;
+            if let Some(backend) = lock_backend().as_mut() {
+                backend.srs_points = backend.srs_points.max(srs_size);
+            }
+            Ok(srs_size.to_le_bytes().to_vec())
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Size in bytes of one G1 point in the backend's `bn254_g1.dat`.
+const SRS_G1_POINT_BYTES: u64 = 64;
+
+/// Directory `bb` loads the SRS from: the configured one (see `srs_path`),
+/// else `CRS_PATH`, else bb's default `~/.bb-crs`.
+fn srs_dir() -> Result<std::path::PathBuf, FfiError> {
+    if let Some((path, _)) = srs_path() {
+        return Ok(path.into());
+    }
+    if let Some(path) = std::env::var_os("CRS_PATH").filter(|p| !p.is_empty()) {
+        return Ok(path.into());
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".bb-crs"))
+        .ok_or_else(|| FfiError::backend("No SRS directory: set one with bb_set_srs_path"))
+}
+
+/// Makes sure proofs of up to `max_gates` gates don't stall on the SRS, for a
+/// batch of circuits of increasing size: the size is rounded up to a power of
+/// two and, unless the backend already loaded that many points, that many
+/// points are read from the SRS directory and loaded into the backend in one
+/// go. The backend then proves from those points only, so pass the largest
+/// circuit: bigger ones fail until the SRS is loaded again. Fails when the
+/// directory holds fewer points (`bb_init_srs_from_bytecode` on the largest
+/// circuit downloads them). Returns the size as a little-endian u32.
+#[no_mangle]
+pub extern "C" fn bb_ensure_srs(max_gates: u32) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let points = max_gates
+                .max(1)
+                .checked_next_power_of_two()
+                .ok_or_else(|| FfiError::invalid_argument(format!("SRS of {} gates is too large", max_gates)))?;
+            if lock_backend().as_ref().is_some_and(|b| b.srs_points >= points) {
+                return Ok(points.to_le_bytes().to_vec());
+            }
+
+            let dir = srs_dir()?;
+            let file = dir.join("bn254_g1.dat");
+            let needed = points as u64 * SRS_G1_POINT_BYTES;
+            let available = std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+            if available < needed {
+                return Err(FfiError::backend(format!(
+                    "SRS at {} has {} points, {} needed",
+                    file.display(),
+                    available / SRS_G1_POINT_BYTES,
+                    points
+                )));
+            }
+
+            let started = std::time::Instant::now();
+            let mut points_buf = vec![0u8; needed as usize];
+            std::fs::File::open(&file).and_then(|mut f| f.read_exact(&mut points_buf)).map_err(FfiError::backend)?;
+            let g2_file = dir.join("bn254_g2.dat");
+            let g2_point = std::fs::read(&g2_file)
+                .map_err(|e| FfiError::backend(format!("Failed to read {}: {}", g2_file.display(), e)))?;
+            match call_bb(Command::SrsInitSrs(barretenberg_rs::generated_types::SrsInitSrs::new(points_buf, points, g2_point)))? {
+                barretenberg_rs::generated_types::Response::SrsInitSrsResponse(_) => {}
+                other => return Err(unexpected_response("SrsInitSrsResponse", &other)),
+            }
+            if let Some(backend) = lock_backend().as_mut() {
+                backend.srs_points = backend.srs_points.max(points);
+            }
+            logging::log(
+                logging::LogLevel::Debug,
+                format!("Loaded {} SRS points from {} in {} ms", points, file.display(), started.elapsed().as_millis()),
+            );
+            Ok(points.to_le_bytes().to_vec())
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct WitnessJson {
+    witness: WitnessValues,
+}
+
+/// Witness values, either dense (`["0x..", ...]`, indexed by position) or
+/// sparse (`{"3": "0x..", "7": "0x.."}`, keyed by witness index like an ACVM
+/// witness map). Values are strings or, for small values, JSON integers.
+enum WitnessValues {
+    Dense(Vec<String>),
+    Sparse(BTreeMap<u32, String>),
+}
+
+impl WitnessValues {
+    fn into_entries(self) -> Vec<(u32, String)> {
+        match self {
+            WitnessValues::Dense(values) => values.into_iter().enumerate().map(|(i, v)| (i as u32, v)).collect(),
+            WitnessValues::Sparse(entries) => entries.into_iter().collect(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WitnessValues {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct WitnessVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for WitnessVisitor {
+            type Value = WitnessValues;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an array of field values or a map from witness index to field value")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(FieldValue(value)) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(WitnessValues::Dense(values))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                use serde::de::Error;
+
+                // Read entry by entry: collecting into a map directly would
+                // silently keep the last of duplicate keys.
+                let mut entries = BTreeMap::new();
+                while let Some((key, FieldValue(value))) = map.next_entry::<String, FieldValue>()? {
+                    let index: u32 = key
+                        .parse()
+                        .map_err(|_| A::Error::custom(format!("Witness index {:?} is not a non-negative integer", key)))?;
+                    if entries.insert(index, value).is_some() {
+                        return Err(A::Error::custom(format!("Duplicate witness index {}", index)));
+                    }
+                }
+                Ok(WitnessValues::Sparse(entries))
+            }
+        }
+
+        deserializer.deserialize_any(WitnessVisitor)
+    }
+}
+
+/// One witness value as written in the JSON: a string for `parse_field`, or
+/// a JSON integer, kept as its decimal string so it takes the same path.
+struct FieldValue(String);
+
+impl<'de> Deserialize<'de> for FieldValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for FieldVisitor {
+            type Value = FieldValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a field value as a string or an integer")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(FieldValue(v.to_string()))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(FieldValue(v))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(FieldValue(v.to_string()))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(FieldValue(v.to_string()))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                // Integers beyond 64 bits arrive here too, already rounded.
+                Err(E::custom(format!("Witness value {} is not an integer that fits in 64 bits; pass it as a string", v)))
+            }
+        }
+
+        deserializer.deserialize_any(FieldVisitor)
+    }
+}
+
+/// BN254 scalar field modulus.
+static BN254_MODULUS: Lazy<BigUint> = Lazy::new(|| {
+    BigUint::parse_bytes(b"21888242871839275222246405745257275088548364400416034343698204186575808495617", 10)
+        .expect("valid BN254 modulus")
+});
+
+/// Whether witness values are checked to be below the field modulus.
+static FIELD_RANGE_CHECK: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables the witness range check (enabled by default). Disable
+/// it only to intentionally pass unreduced values to the backend.
+#[no_mangle]
+pub extern "C" fn bb_set_field_range_check(enabled: bool) {
+    FIELD_RANGE_CHECK.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether hex witness values are little-endian rather than big-endian.
+static FIELD_LITTLE_ENDIAN: AtomicBool = AtomicBool::new(false);
+
+/// Selects the byte order of `0x` hex witness values: big-endian by default,
+/// or little-endian for tools that emit it. Decimal values are unaffected,
+/// and so are public inputs, which this library always emits big-endian.
+#[no_mangle]
+pub extern "C" fn bb_set_field_endianness(little_endian: bool) {
+    FIELD_LITTLE_ENDIAN.store(little_endian, Ordering::Relaxed);
+}
+
+/// Modulus of the field witness values live in, set by
+/// `bb_set_field_modulus`; `None` is BN254's scalar field.
+static FIELD_MODULUS: std::sync::RwLock<Option<std::sync::Arc<BigUint>>> = std::sync::RwLock::new(None);
+
+fn field_modulus() -> std::sync::Arc<BigUint> {
+    FIELD_MODULUS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| std::sync::Arc::new(BN254_MODULUS.clone()))
+}
+
+/// Sets the field modulus used to range-check witness values and to reduce
+/// negative decimal values, for circuits over a field other than BN254's
+/// scalar field (e.g. Grumpkin's, which is BN254's base field). Takes the
+/// modulus as hex, with or without `0x`; null or an empty string restores
+/// BN254. Field elements stay 32 bytes, so the modulus must fit in 256 bits.
+#[no_mangle]
+pub extern "C" fn bb_set_field_modulus(modulus_hex: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let hex_str = if modulus_hex.is_null() { String::new() } else { unsafe { cstr_to_string(modulus_hex) }? };
+            let hex_str = hex_str.strip_prefix("0x").unwrap_or(&hex_str);
+            let modulus = if hex_str.is_empty() {
+                None
+            } else {
+                let modulus = BigUint::parse_bytes(hex_str.as_bytes(), 16)
+                    .ok_or_else(|| FfiError::invalid_argument(format!("Invalid hex field modulus {:?}", hex_str)))?;
+                if modulus < BigUint::from(2u32) || modulus.bits() > 256 {
+                    return Err(FfiError::invalid_argument("Field modulus must be between 2 and 2^256"));
+                }
+                Some(std::sync::Arc::new(modulus))
+            };
+            *FIELD_MODULUS.write().unwrap_or_else(|e| e.into_inner()) = modulus;
+            Ok(())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+fn is_in_field(field_bytes: &[u8; 32]) -> bool {
+    BigUint::from_bytes_be(field_bytes) < *field_modulus()
+}
+
+/// Parses a field value; `0x` hex is little-endian if `little_endian` is set.
+fn parse_field(s: &str, little_endian: bool) -> Result<[u8; 32], String> {
+    let bytes = if s.starts_with("0x") {
+        let hex_str = &s[2..];
+        let mut decoded = vec![0u8; 32];
+        let mut h = hex::decode(hex_str).map_err(|e| e.to_string())?;
+        if little_endian {
+            h.reverse();
+        }
+        if h.len() > 32 {
+            return Err("Hex string too long for field element".into());
+        }
+        let offset = 32 - h.len();
+        decoded[offset..].copy_from_slice(&h);
+        decoded
+    } else if let Some(magnitude) = s.strip_prefix('-') {
+        // Signed decimal: -m encodes p - m.
+        let magnitude = magnitude.parse::<BigUint>().map_err(|e| e.to_string())?;
+        let modulus = field_modulus();
+        if magnitude >= *modulus {
+            return Err("Negative value magnitude exceeds field modulus".into());
+        }
+        let val = (&*modulus - magnitude) % &*modulus;
+        let b = val.to_bytes_be();
+        let mut decoded = vec![0u8; 32];
+        decoded[32 - b.len()..].copy_from_slice(&b);
+        decoded
+    } else {
+        let val = s.parse::<BigUint>().map_err(|e| e.to_string())?;
+        let b = val.to_bytes_be();
+        if b.len() > 32 {
+            return Err("Decimal value too large for field element".into());
+        }
+        let mut decoded = vec![0u8; 32];
+        decoded[32 - b.len()..].copy_from_slice(&b);
+        decoded
+    };
+    
+    let mut arr = [0u8; 32];
+    arr.copy_from_slice(&bytes);
+    Ok(arr)
+}
+
+/// Format byte in front of a serialized witness stack: msgpack.
+const WITNESS_STACK_FORMAT: u8 = 2;
+
+#[derive(Serialize)]
+struct WitnessMapWrapper(BTreeMap<u32, serde_bytes::ByteBuf>);
+
+#[derive(Serialize)]
+struct StackItemWrapper(u32, WitnessMapWrapper);
+
+#[derive(Serialize)]
+struct FinalWitnessStack {
+    stack: Vec<StackItemWrapper>,
+}
+
+/// Builds the serialized witness stack expected by `CircuitProve` from the
+/// witness of a single circuit.
+fn encode_witness(witness: WitnessValues) -> Result<Vec<u8>, FfiError> {
+    encode_witness_stack(vec![(0, witness)])
+}
+
+/// Parses a `{"witness": ...}` argument and encodes it as the backend expects.
+unsafe fn encode_witness_json(witness_json: *const c_char) -> Result<Vec<u8>, FfiError> {
+    let wj_str = cstr_to_string(witness_json)?;
+    encode_witness_str(&wj_str)
+}
+
+fn encode_witness_str(wj_str: &str) -> Result<Vec<u8>, FfiError> {
+    let parsed: WitnessJson = serde_json::from_str(wj_str).map_err(FfiError::invalid_argument)?;
+    encode_witness(parsed.witness)
+}
+
+fn witness_field(index: u32, val_str: &str, range_check: bool) -> Result<[u8; 32], FfiError> {
+    let field_bytes = parse_field(val_str, FIELD_LITTLE_ENDIAN.load(Ordering::Relaxed)).map_err(|e| {
+        FfiError::invalid_argument(format!("Failed to parse witness[{}]: {}", index, e)).with_context("witness_index", index)
+    })?;
+    if range_check && !is_in_field(&field_bytes) {
+        return Err(FfiError::invalid_argument(format!(
+            "Field element out of range at witness index {}: value is not below the field modulus",
+            index
+        ))
+        .with_context("witness_index", index));
+    }
+    Ok(field_bytes)
+}
+
+fn encode_witness_stack(items: Vec<(u32, WitnessValues)>) -> Result<Vec<u8>, FfiError> {
+    let range_check = FIELD_RANGE_CHECK.load(Ordering::Relaxed);
+    let mut stack = Vec::with_capacity(items.len());
+    for (circuit_index, witness) in items {
+        let mut witness_map = BTreeMap::new();
+        for (i, val_str) in witness.into_entries() {
+            let field_bytes = witness_field(i, &val_str, range_check)?;
+            witness_map.insert(i, serde_bytes::ByteBuf::from(field_bytes.to_vec()));
+        }
+        stack.push(StackItemWrapper(circuit_index, WitnessMapWrapper(witness_map)));
+    }
+
+    let final_stack = FinalWitnessStack { stack };
+
+    let encoded = rmp_serde::to_vec(&final_stack)
+        .map_err(|e| FfiError::serialization(format!("Failed to serialize witness stack: {}", e)))?;
+    let mut witness_bytes = vec![WITNESS_STACK_FORMAT];
+    witness_bytes.extend(encoded);
+    Ok(witness_bytes)
+}
+
+/// Whether a pipe backend error means the `bb` subprocess is gone, as opposed
+/// to the command itself failing.
+fn is_broken_pipe(e: &FfiError) -> bool {
+    let msg = e.message.to_lowercase();
+    msg.contains("broken pipe") || msg.contains("unexpected end of file") || msg.contains("unexpected eof")
+}
+
+/// Timeout for a single pipe backend call in milliseconds, 0 meaning none.
+static CALL_TIMEOUT_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Sets the timeout for pipe backend calls; 0 disables it. The clock starts
+/// once the call holds the backend, so time queued behind other calls doesn't
+/// count. A call that times out returns a `Timeout` error and its `bb`
+/// subprocess is killed, so the next call respawns it. The native backend
+/// cannot be interrupted, so its calls are not timed.
+#[no_mangle]
+pub extern "C" fn bb_set_call_timeout_ms(timeout_ms: u32) {
+    CALL_TIMEOUT_MS.store(timeout_ms, Ordering::Relaxed);
+}
+
+fn call_bb(cmd: Command) -> Result<barretenberg_rs::generated_types::Response, FfiError> {
+    metrics::inc(&metrics::BACKEND_CALLS);
+    let watch = JOB_WATCH.with(|w| w.borrow().clone()).unwrap_or_default();
+    let threads = CALL_OPTIONS.with(|o| o.borrow().threads);
+
+    let mut backend = get_api()?;
+    backend.output.clear();
+    let threads = threads.unwrap_or_else(|| backend.configured_threads());
+    backend.use_threads(threads)?;
+    let call = watch.begin(&backend)?;
+    let timeout_ms = CALL_TIMEOUT_MS.load(Ordering::Relaxed);
+    if timeout_ms != 0 && backend.api.is_pipe() {
+        watchdog(Deadline {
+            at: std::time::Instant::now() + Duration::from_millis(timeout_ms as u64),
+            timeout_ms,
+            watch: Arc::downgrade(&watch),
+            call,
+        });
+    }
+
+    let res = dispatch_retrying(&mut backend, cmd, &watch);
+    // The output is complete here: the pipe backend drains it before returning.
+    watch.finish(res).map_err(|e| {
+        let output = backend.output.text();
+        if e.code == ErrorCode::Backend && !output.is_empty() {
+            e.with_context("backend_output", output)
+        } else {
+            e
+        }
+    })
+}
+
+/// When the call `call` on `watch` times out.
+struct Deadline {
+    at: std::time::Instant,
+    timeout_ms: u32,
+    watch: std::sync::Weak<CallWatch>,
+    call: u64,
+}
+
+/// Hands `deadline` to the one thread that times out backend calls.
+fn watchdog(deadline: Deadline) {
+    static WATCHDOG: Lazy<std::sync::Mutex<mpsc::Sender<Deadline>>> = Lazy::new(|| {
+        let (tx, rx) = mpsc::channel::<Deadline>();
+        std::thread::spawn(move || {
+            let mut deadlines: Vec<Deadline> = Vec::new();
+            loop {
+                let received = match deadlines.iter().map(|d| d.at).min() {
+                    Some(at) => rx.recv_timeout(at.saturating_duration_since(std::time::Instant::now())),
+                    None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(deadline) => deadlines.push(deadline),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+
+                let now = std::time::Instant::now();
+                deadlines.retain(|d| match d.watch.upgrade() {
+                    Some(watch) if d.at <= now => {
+                        watch.time_out(d.call, d.timeout_ms);
+                        false
+                    }
+                    Some(watch) => watch.is_running(d.call),
+                    None => false,
+                });
+            }
+        });
+        std::sync::Mutex::new(tx)
+    });
+
+    let _ = WATCHDOG.lock().unwrap_or_else(|e| e.into_inner()).send(deadline);
+}
+
+/// Why another thread abandoned a backend call.
+#[derive(Clone, Copy)]
+enum Abandoned {
+    /// After the timeout in milliseconds.
+    TimedOut(u32),
+    Cancelled,
+}
+
+impl Abandoned {
+    fn error(self) -> FfiError {
+        match self {
+            Abandoned::TimedOut(ms) => FfiError::timeout(format!("Backend call timed out after {} ms", ms)),
+            Abandoned::Cancelled => FfiError::cancelled("Backend call cancelled"),
+        }
+    }
+}
+
+/// Lets another thread abandon a backend call. The `bb` subprocess serving it
+/// is only killed under this lock, which the call also takes to stop
+/// publishing the pid, so a kill never reaches a subprocess that has moved on
+/// to another caller's command.
+#[derive(Default)]
+struct CallWatch(std::sync::Mutex<WatchState>);
+
+#[derive(Default)]
+struct WatchState {
+    abandoned: Option<Abandoned>,
+    /// Number of the latest call through this watch, and whether it runs.
+    call: u64,
+    running: bool,
+    /// Pid of the `bb` subprocess serving the call.
+    pid: Option<u32>,
+    /// Set while the call runs on the native backend, which cannot be
+    /// interrupted.
+    native: bool,
+}
+
+impl CallWatch {
+    fn state(&self) -> std::sync::MutexGuard<'_, WatchState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Starts a call on `backend` and returns its number, or fails if the
+    /// watch was abandoned already.
+    fn begin(&self, backend: &Backend) -> Result<u64, FfiError> {
+        self.serve(backend)?;
+        let mut state = self.state();
+        state.call += 1;
+        state.running = true;
+        Ok(state.call)
+    }
+
+    fn is_running(&self, call: u64) -> bool {
+        let state = self.state();
+        state.running && state.call == call
+    }
+
+    /// Publishes the backend serving the call, or fails if the call was
+    /// abandoned already.
+    fn serve(&self, backend: &Backend) -> Result<(), FfiError> {
+        let mut state = self.state();
+        if let Some(reason) = state.abandoned {
+            return Err(reason.error());
+        }
+        state.pid = backend.pid;
+        state.native = !backend.api.is_pipe();
+        Ok(())
+    }
+
+    /// Stops publishing the backend, e.g. before its subprocess is reaped.
+    fn detach(&self) {
+        let mut state = self.state();
+        state.pid = None;
+        state.native = false;
+    }
+
+    /// Detaches the call once done. A call that failed because it was
+    /// abandoned reports why.
+    fn finish<T>(&self, res: Result<T, FfiError>) -> Result<T, FfiError> {
+        self.detach();
+        self.state().running = false;
+        match (res, self.state().abandoned) {
+            (Err(_), Some(reason)) => Err(reason.error()),
+            (res, _) => res,
+        }
+    }
+
+    /// Abandons the call, killing the subprocess serving it if there is one.
+    /// A cancel fails instead while the call runs on the native backend.
+    fn abandon(&self, reason: Abandoned) -> Result<(), FfiError> {
+        let mut state = self.state();
+        if state.native && matches!(reason, Abandoned::Cancelled) {
+            return Err(FfiError::cancelled("Cancellation unsupported by the native backend"));
+        }
+        state.abandon(reason);
+        Ok(())
+    }
+
+    /// Abandons call `call` if it is still running after `timeout_ms`.
+    fn time_out(&self, call: u64, timeout_ms: u32) {
+        {
+            let mut state = self.state();
+            if !(state.running && state.call == call) {
+                return;
+            }
+            state.abandon(Abandoned::TimedOut(timeout_ms));
+        }
+        logging::log(logging::LogLevel::Warn, format!("Backend call timed out after {} ms", timeout_ms));
+    }
+}
+
+impl WatchState {
+    fn abandon(&mut self, reason: Abandoned) {
+        self.abandoned = Some(reason);
+        if let Some(pid) = self.pid {
+            process::kill(pid);
+        }
+    }
+}
+
+thread_local! {
+    /// Watch of the job running on this thread, if any; its backend calls
+    /// report to it so `bb_prove_cancel` can reach them.
+    static JOB_WATCH: std::cell::RefCell<Option<Arc<CallWatch>>> = const { std::cell::RefCell::new(None) };
+}
+
+fn dispatch_retrying(
+    backend: &mut Backend,
+    cmd: Command,
+    watch: &CallWatch,
+) -> Result<barretenberg_rs::generated_types::Response, FfiError> {
+    // A dead `bb` subprocess is respawned once and the command retried.
+    let retry_cmd = backend.api.is_pipe().then(|| cmd.clone());
+    match (dispatch(&mut backend.api, cmd), retry_cmd) {
+        (Err(e), Some(retry_cmd)) if is_broken_pipe(&e) => {
+            logging::log(logging::LogLevel::Warn, format!("bb subprocess lost ({}), respawning", e));
+            // The old subprocess is reaped by the respawn, after which its
+            // pid may be reused.
+            watch.detach();
+            if let Err(e) = backend.reconnect() {
+                logging::log(logging::LogLevel::Error, format!("Failed to respawn bb subprocess: {}", e));
+                backend.poisoned = true;
+                return Err(e);
+            }
+            // Killed by a timeout or a cancel: don't run the command again.
+            watch.serve(backend)?;
+            dispatch(&mut backend.api, retry_cmd)
+        }
+        (res, _) => res,
+    }
+}
+
+/// Runs `cmd` on `api`, a `BarretenbergApi` of any backend. A macro rather
+/// than a function so the same arms serve every `ApiEnum` variant; new
+/// commands are added here once.
+macro_rules! dispatch_command {
+    ($api:expr, $cmd:expr) => {{
+        use barretenberg_rs::generated_types::Response;
+        let api = $api;
+        match $cmd {
+            Command::CircuitComputeVk(data) => api
+                .circuit_compute_vk(data.circuit, data.settings)
+                .map(Response::CircuitComputeVkResponse)
+                .map_err(FfiError::backend),
+            Command::CircuitProve(data) => api
+                .circuit_prove(data.circuit, &data.witness, data.settings)
+                .map(Response::CircuitProveResponse)
+                .map_err(FfiError::backend),
+            Command::CircuitVerify(data) => api
+                .circuit_verify(&data.verification_key, data.public_inputs, data.proof, data.settings)
+                .map(Response::CircuitVerifyResponse)
+                .map_err(FfiError::backend),
+            Command::CircuitStats(data) => api
+                .circuit_stats(data.circuit, data.include_gates_per_opcode, data.settings)
+                .map(Response::CircuitInfoResponse)
+                .map_err(FfiError::backend),
+            Command::CircuitWriteSolidityVerifier(data) => api
+                .circuit_write_solidity_verifier(&data.verification_key, data.settings)
+                .map(Response::CircuitWriteSolidityVerifierResponse)
+                .map_err(FfiError::backend),
+            Command::VkAsFields(data) => api
+                .vk_as_fields(&data.verification_key)
+                .map(Response::VkAsFieldsResponse)
+                .map_err(FfiError::backend),
+            Command::ChonkComputeStandaloneVk(data) => api
+                .chonk_compute_standalone_vk(data.circuit)
+                .map(Response::ChonkComputeStandaloneVkResponse)
+                .map_err(FfiError::backend),
+            Command::ChonkStart(data) => api
+                .chonk_start(data.num_circuits)
+                .map(Response::ChonkStartResponse)
+                .map_err(FfiError::backend),
+            Command::ChonkLoad(data) => api
+                .chonk_load(data.circuit)
+                .map(Response::ChonkLoadResponse)
+                .map_err(FfiError::backend),
+            Command::ChonkAccumulate(data) => api
+                .chonk_accumulate(&data.witness)
+                .map(Response::ChonkAccumulateResponse)
+                .map_err(FfiError::backend),
+            Command::ChonkProve(_) => api
+                .chonk_prove()
+                .map(Response::ChonkProveResponse)
+                .map_err(FfiError::backend),
+            _ => Err(FfiError::backend("Unsupported command")),
+        }
+    }};
+}
+
+/// Name of a response variant, for error messages.
+fn response_name(resp: &barretenberg_rs::generated_types::Response) -> &'static str {
+    use barretenberg_rs::generated_types::Response;
+    match resp {
+        Response::CircuitComputeVkResponse(_) => "CircuitComputeVkResponse",
+        Response::CircuitProveResponse(_) => "CircuitProveResponse",
+        Response::CircuitVerifyResponse(_) => "CircuitVerifyResponse",
+        Response::CircuitInfoResponse(_) => "CircuitInfoResponse",
+        Response::CircuitWriteSolidityVerifierResponse(_) => "CircuitWriteSolidityVerifierResponse",
+        Response::VkAsFieldsResponse(_) => "VkAsFieldsResponse",
+        Response::ChonkComputeStandaloneVkResponse(_) => "ChonkComputeStandaloneVkResponse",
+        Response::ChonkStartResponse(_) => "ChonkStartResponse",
+        Response::ChonkLoadResponse(_) => "ChonkLoadResponse",
+        Response::ChonkAccumulateResponse(_) => "ChonkAccumulateResponse",
+        Response::ChonkProveResponse(_) => "ChonkProveResponse",
+        _ => "an unrecognized response",
+    }
+}
+
+/// Error for a response of the wrong kind. Failures reported by the backend
+/// never get here: `dispatch` already turns them into errors carrying the
+/// backend's message.
+fn unexpected_response(expected: &str, got: &barretenberg_rs::generated_types::Response) -> FfiError {
+    FfiError::backend(format!("Unexpected backend response: expected {}, got {}", expected, response_name(got)))
+}
+
+fn dispatch(api: &mut ApiEnum, cmd: Command) -> Result<barretenberg_rs::generated_types::Response, FfiError> {
+    match api {
+        #[cfg(unix)]
+        ApiEnum::Pipe(api) => dispatch_command!(api, cmd),
+        #[cfg(feature = "native-backend")]
+        ApiEnum::Native(api) => dispatch_command!(api, cmd),
+    }
+}
+
+const DEFAULT_CIRCUIT_NAME: &str = "circuit";
+const SELF_TEST_CIRCUIT_NAME: &str = "self_test";
+
+/// Options of the entrypoint running on this thread, from its settings.
+#[derive(Clone, Default)]
+struct CallOptions {
+    /// Circuit name for the UltraHonk commands it sends.
+    circuit_name: Option<String>,
+    /// Thread count its backend commands run with, from `"threads"`.
+    threads: Option<usize>,
+}
+
+thread_local! {
+    /// The entrypoint's `CallScope` puts the previous options back when it
+    /// returns, so options never outlive their call.
+    static CALL_OPTIONS: std::cell::RefCell<CallOptions> =
+        const { std::cell::RefCell::new(CallOptions { circuit_name: None, threads: None }) };
+}
+
+/// Saves the per-call options of this thread and restores them when dropped.
+/// If the scope overrode the thread count, the backend goes back to its
+/// configured count then, so the override doesn't outlive the call. A backend
+/// busy with another call is left alone, as that call sets its own count.
+struct CallScope(CallOptions);
+
+impl CallScope {
+    fn enter() -> Self {
+        CallScope(CALL_OPTIONS.with(|o| o.borrow().clone()))
+    }
+}
+
+impl Drop for CallScope {
+    fn drop(&mut self) {
+        let overrode_threads = CALL_OPTIONS.with(|o| {
+            let mut options = o.borrow_mut();
+            let overrode = self.0.threads.is_none() && options.threads.is_some();
+            *options = std::mem::take(&mut self.0);
+            overrode
+        });
+        if !overrode_threads {
+            return;
+        }
+        if let Ok(Some(backend)) = current_slot().try_lock().as_deref_mut() {
+            let threads = backend.configured_threads();
+            if let Err(e) = backend.use_threads(threads) {
+                logging::log(logging::LogLevel::Warn, format!("Failed to restore {} backend threads: {}", threads, e.message));
+            }
+        }
+    }
+}
+
+fn set_circuit_name(name: Option<String>) {
+    CALL_OPTIONS.with(|o| o.borrow_mut().circuit_name = name);
+}
+
+/// Runs `f` with `name` as the circuit name, then restores the caller's.
+fn with_circuit_name<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let _scope = CallScope::enter();
+    set_circuit_name(Some(name.to_string()));
+    f()
+}
+
+/// Name to send in `CircuitInput`s, shown in the backend's diagnostics.
+fn circuit_name() -> String {
+    CALL_OPTIONS.with(|o| o.borrow().circuit_name.clone()).unwrap_or_else(|| DEFAULT_CIRCUIT_NAME.to_string())
+}
+
+fn compute_vk(bytecode: Vec<u8>, settings: ProofSystemSettings) -> Result<CircuitComputeVkResponse, FfiError> {
+    let circuit_input = CircuitInputNoVK {
+        name: circuit_name(),
+        bytecode,
+    };
+
+    metrics::inc(&metrics::VKS_COMPUTED);
+    match call_bb(Command::CircuitComputeVk(barretenberg_rs::generated_types::CircuitComputeVk::new(circuit_input, settings)))? {
+        barretenberg_rs::generated_types::Response::CircuitComputeVkResponse(r) => Ok(r),
+        other => Err(unexpected_response("CircuitComputeVkResponse", &other)),
+    }
+}
+
+/// Returns the VK for the bytecode/settings pair, computing it through the
+/// backend only on a cache miss. Concurrent misses for the same pair compute
+/// it once and the other callers take it from the cache; if the computation
+/// fails, the next waiter tries again.
+fn cached_vk(bytecode: &[u8], settings: &ProofSystemSettings) -> Result<Vec<u8>, FfiError> {
+    let key = vk_cache_key(bytecode, settings)?;
+    if let Some(vk) = VK_CACHE.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return Ok(vk);
+    }
+
+    let flight = VK_IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner()).entry(key).or_default().clone();
+    let res = {
+        let _computing = flight.lock().unwrap_or_else(|e| e.into_inner());
+        // Filled in while we waited for another caller's computation.
+        match VK_CACHE.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            Some(vk) => Ok(vk),
+            None => compute_cached_vk(key, bytecode, settings),
+        }
+    };
+
+    // Handles are only cloned and dropped under the map lock, so the last
+    // caller out sees the map's as the only one left.
+    let mut in_flight = VK_IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+    drop(flight);
+    if in_flight.get(&key).is_some_and(|f| Arc::strong_count(f) == 1) {
+        in_flight.remove(&key);
+    }
+    res
+}
+
+fn compute_cached_vk(key: vk_cache::CacheKey, bytecode: &[u8], settings: &ProofSystemSettings) -> Result<Vec<u8>, FfiError> {
+    progress::report(progress::Phase::ComputingVk);
+    let (vk, vk_ms) = timing::measure(|| compute_vk(bytecode.to_vec(), settings.clone()));
+    timing::add_vk_ms(vk_ms);
+    let vk = vk?.bytes;
+    VK_CACHE.write().unwrap_or_else(|e| e.into_inner()).insert(key, vk.clone());
+    Ok(vk)
+}
+
+fn vk_cache_key(bytecode: &[u8], settings: &ProofSystemSettings) -> Result<vk_cache::CacheKey, FfiError> {
+    let settings_json = serde_json::to_vec(settings).map_err(FfiError::serialization)?;
+    Ok(vk_cache::cache_key(bytecode, &settings_json))
+}
+
+#[no_mangle]
+pub extern "C" fn bb_clear_vk_cache() {
+    ffi_boundary_or((), || {
+        VK_CACHE.write().unwrap_or_else(|e| e.into_inner()).clear();
+    })
+}
+
+/// Number of public inputs reserved in every UltraHonk VK for the pairing
+/// point accumulator, on top of the circuit's own public inputs.
+const PAIRING_POINTS_SIZE: u64 = 16;
+
+/// Number of public inputs reserved for the IPA claim when `ipa_accumulation`
+/// is enabled.
+const IPA_CLAIM_SIZE: u64 = 10;
+
+/// Reads the number of circuit public inputs from a serialized UltraHonk VK.
+/// The VK starts with 32-byte big-endian fields `[log_circuit_size,
+/// num_public_inputs, pub_inputs_offset, ...]`, where `num_public_inputs`
+/// also counts the backend's reserved accumulator inputs.
+fn vk_num_public_inputs(vk: &[u8], settings: &ProofSystemSettings) -> Result<u64, FfiError> {
+    let field = vk
+        .get(32..64)
+        .ok_or_else(|| FfiError::decode("Verification key too short"))?;
+    if field[..24].iter().any(|b| *b != 0) {
+        return Err(FfiError::decode("Invalid public input count in verification key"));
+    }
+    let mut count = [0u8; 8];
+    count.copy_from_slice(&field[24..]);
+    let total = u64::from_be_bytes(count);
+
+    total
+        .checked_sub(reserved_public_inputs(settings))
+        .ok_or_else(|| FfiError::decode("Invalid public input count in verification key"))
+}
+
+/// Number of public inputs the backend appends for its own accumulators.
+fn reserved_public_inputs(settings: &ProofSystemSettings) -> u64 {
+    PAIRING_POINTS_SIZE + if settings.ipa_accumulation { IPA_CLAIM_SIZE } else { 0 }
+}
+
+fn vk_as_fields(vk: Vec<u8>) -> Result<Vec<String>, FfiError> {
+    match call_bb(Command::VkAsFields(barretenberg_rs::generated_types::VkAsFields::new(vk)))? {
+        barretenberg_rs::generated_types::Response::VkAsFieldsResponse(r) => {
+            Ok(r.fields.iter().map(|f| format!("0x{}", hex::encode(f))).collect())
+        }
+        other => Err(unexpected_response("VkAsFieldsResponse", &other)),
+    }
+}
+
+fn circuit_stats(bytecode: Vec<u8>, settings: ProofSystemSettings) -> Result<CircuitInfoResponse, FfiError> {
+    let circuit_input = CircuitInput {
+        name: circuit_name(),
+        bytecode,
+        verification_key: vec![],
+    };
+
+    match call_bb(Command::CircuitStats(barretenberg_rs::generated_types::CircuitStats::new(circuit_input, false, settings)))? {
+        barretenberg_rs::generated_types::Response::CircuitInfoResponse(r) => Ok(r),
+        other => Err(unexpected_response("CircuitInfoResponse", &other)),
+    }
+}
+
+fn prove(
+    bytecode: Vec<u8>,
+    vk: Vec<u8>,
+    witness_bytes: Vec<u8>,
+    settings: ProofSystemSettings,
+) -> Result<CircuitProveResponse, FfiError> {
+    check_witness_count(&bytecode, &witness_bytes)?;
+    let key = vk_cache_key(&bytecode, &settings)?;
+    let circuit_input = CircuitInput {
+        name: circuit_name(),
+        bytecode,
+        verification_key: vk,
+    };
+
+    metrics::inc(&metrics::PROOFS_STARTED);
+    progress::report(progress::Phase::Proving);
+    let (resp, prove_ms) = timing::measure(|| {
+        call_bb(Command::CircuitProve(barretenberg_rs::generated_types::CircuitProve::new(circuit_input, witness_bytes, settings)))
+    });
+    let res = match resp {
+        Ok(barretenberg_rs::generated_types::Response::CircuitProveResponse(r)) => {
+            timing::finish(prove_ms);
+            progress::report(progress::Phase::Done);
+            PROOF_LENGTHS.lock().unwrap_or_else(|e| e.into_inner()).insert(key, r.proof.len() * 32);
+            Ok(r)
+        }
+        Ok(other) => Err(unexpected_response("CircuitProveResponse", &other)),
+        Err(e) => Err(e),
+    };
+    metrics::inc(if res.is_ok() { &metrics::PROOFS_SUCCEEDED } else { &metrics::PROOFS_FAILED });
+    res
+}
+
+/// Shared front half of the string-based prove entrypoints: decodes the
+/// arguments, then computes (or reuses) the VK and proves.
+fn prove_ultrahonk(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> Result<CircuitProveResponse, FfiError> {
+    timing::start();
+    let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+    let bytecode = decode_bytecode(&bytecode_str)?;
+    prove_bytecode(bytecode, witness_json, settings_json)
+}
+
+/// Back half of `prove_ultrahonk`, for bytecode that is already decoded.
+fn prove_bytecode(
+    bytecode: Vec<u8>,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> Result<CircuitProveResponse, FfiError> {
+    let settings = unsafe { parse_settings(settings_json) }?;
+    prove_bytecode_with(bytecode, witness_json, settings)
+}
+
+/// `prove_bytecode` with the settings already parsed.
+fn prove_bytecode_with(
+    bytecode: Vec<u8>,
+    witness_json: *const c_char,
+    settings: ProofSystemSettings,
+) -> Result<CircuitProveResponse, FfiError> {
+    let wj_str = unsafe { cstr_to_string(witness_json) }?;
+    prove_bytecode_with_str(bytecode, &wj_str, settings)
+}
+
+/// `prove_bytecode_with` for witness JSON that is already a Rust string.
+fn prove_bytecode_with_str(
+    bytecode: Vec<u8>,
+    wj_str: &str,
+    settings: ProofSystemSettings,
+) -> Result<CircuitProveResponse, FfiError> {
+    let parsed: WitnessJson = serde_json::from_str(wj_str).map_err(FfiError::invalid_argument)?;
+    let vk = cached_vk(&bytecode, &settings)?;
+    let witness_bytes = encode_witness(parsed.witness)?;
+    prove(bytecode, vk, witness_bytes, settings)
+}
+
+/// Decodes an ACIR program as a generic msgpack value. Only msgpack-serialized
+/// programs are understood, with or without the leading format byte; `None`
+/// otherwise.
+fn acir_program(bytecode: &[u8]) -> Option<rmpv::Value> {
+    [bytecode, bytecode.get(1..)?].into_iter().find_map(|buf| {
+        let mut cursor = std::io::Cursor::new(buf);
+        let value = rmpv::decode::read_value(&mut cursor).ok()?;
+        (cursor.position() == buf.len() as u64).then_some(value)
+    })
+}
+
+/// A struct field of a decoded ACIR value, serialized either as a map keyed
+/// by field name or as an array in declaration order.
+fn acir_field<'a>(value: &'a rmpv::Value, name: &str, position: usize) -> Option<&'a rmpv::Value> {
+    match value {
+        rmpv::Value::Map(entries) => entries.iter().find(|(k, _)| k.as_str() == Some(name)).map(|(_, v)| v),
+        rmpv::Value::Array(items) => items.get(position),
+        _ => None,
+    }
+}
+
+/// Number of witnesses of the program's main circuit, i.e. its
+/// `current_witness_index` plus one, or `None` if the bytecode isn't a
+/// msgpack program.
+fn acir_witness_count(bytecode: &[u8]) -> Option<u32> {
+    let program = acir_program(bytecode)?;
+    let main = acir_field(&program, "functions", 0)?.as_array()?.first()?;
+    // Circuit fields in order: function_name, current_witness_index, ...
+    let current_witness_index = acir_field(main, "current_witness_index", 1)?.as_u64()?;
+    u32::try_from(current_witness_index).ok()?.checked_add(1)
+}
+
+/// Number of entries and highest index of the witness a serialized stack
+/// holds for the main circuit (circuit index 0), read without decoding the
+/// values. `None` if it has no such witness or isn't laid out the way
+/// `encode_witness_stack` writes it.
+fn main_witness_indices(witness_bytes: &[u8]) -> Option<(u32, Option<u32>)> {
+    let mut rd = witness_bytes.strip_prefix(&[WITNESS_STACK_FORMAT])?;
+    // [[[circuit_index, {witness_index: bin, ...}], ...]]
+    if rmp::decode::read_array_len(&mut rd).ok()? != 1 {
+        return None;
+    }
+    for _ in 0..rmp::decode::read_array_len(&mut rd).ok()? {
+        if rmp::decode::read_array_len(&mut rd).ok()? != 2 {
+            return None;
+        }
+        let circuit_index: u32 = rmp::decode::read_int(&mut rd).ok()?;
+        let len = rmp::decode::read_map_len(&mut rd).ok()?;
+        let mut max = None;
+        for _ in 0..len {
+            let index: u32 = rmp::decode::read_int(&mut rd).ok()?;
+            let value_len = rmp::decode::read_bin_len(&mut rd).ok()? as usize;
+            rd = rd.get(value_len..)?;
+            max = max.max(Some(index));
+        }
+        if circuit_index == 0 {
+            return Some((len, max));
+        }
+    }
+    None
+}
+
+/// Rejects a witness that can't fit the circuit with a plain error, before
+/// the backend fails on it obscurely. A witness with no gaps in its indices
+/// is taken as dense and must have exactly one value per circuit witness; a
+/// sparse one must not go past the last. Skipped when the circuit's witness
+/// count or the witness's indices can't be read.
+fn check_witness_count(bytecode: &[u8], witness_bytes: &[u8]) -> Result<(), FfiError> {
+    let (Some(expected), Some((len, max))) = (acir_witness_count(bytecode), main_witness_indices(witness_bytes)) else {
+        return Ok(());
+    };
+    let dense = max.map_or(true, |index| index as u64 + 1 == len as u64);
+    if dense && len != expected {
+        return Err(FfiError::invalid_argument(format!("Witness length mismatch: expected {}, got {}", expected, len)));
+    }
+    match max {
+        Some(index) if index >= expected => Err(FfiError::invalid_argument(format!(
+            "Witness length mismatch: index {} is beyond the circuit's {} witnesses",
+            index, expected
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// `bb_prove_ultrahonk` with the oracle hash forced to `oracle_hash_type`,
+/// keeping the other settings.
+fn prove_ultrahonk_with_oracle(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+    oracle_hash_type: &str,
+) -> BBResult {
+    let res: Result<Vec<u8>, FfiError> = (|| {
+        timing::start();
+        let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+        let bytecode = decode_bytecode(&bytecode_str)?;
+        let mut settings = unsafe { parse_settings(settings_json) }?;
+        settings.oracle_hash_type = oracle_hash_type.to_string();
+
+        let prove_resp = prove_bytecode_with(bytecode, witness_json, settings)?;
+        rmp_serde::to_vec_named(&prove_resp)
+            .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+    })();
+
+    match res {
+        Ok(v) => ok(v),
+        Err(e) => err(e),
+    }
+}
+
+/// `bb_prove_ultrahonk` with the Keccak oracle hash, as needed for proofs
+/// verified on-chain by a Solidity verifier.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_keccak(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        prove_ultrahonk_with_oracle(bytecode_b64_gz, witness_json, settings_json, "keccak")
+    })
+}
+
+/// `bb_prove_ultrahonk` with the Poseidon2 oracle hash, for proofs verified
+/// off-chain or recursively in a circuit.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_poseidon(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        prove_ultrahonk_with_oracle(bytecode_b64_gz, witness_json, settings_json, "poseidon2")
+    })
+}
+
+fn prove_ultrahonk_msgpack(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> Result<Vec<u8>, FfiError> {
+    let prove_resp = prove_ultrahonk(bytecode_b64_gz, witness_json, settings_json)?;
+
+    let resp_bytes = rmp_serde::to_vec_named(&prove_resp)
+        .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))?;
+
+    Ok(resp_bytes)
+}
+
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        match prove_ultrahonk_msgpack(bytecode_b64_gz, witness_json, settings_json) {
+            Ok(p) => ok(p),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Like `bb_prove_ultrahonk`, but also returns the VK the proof was made
+/// with, saving a `bb_get_vk_ultrahonk` call. Layout of the returned buffer:
+///
+/// ```text
+/// proof_len: u32 little-endian
+/// proof_len bytes: msgpack proof response, as from bb_prove_ultrahonk
+/// rest: VK bytes
+/// ```
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_with_vk_out(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let witness_bytes = unsafe { encode_witness_json(witness_json) }?;
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            let prove_resp = prove(bytecode, vk.clone(), witness_bytes, settings)?;
+            let proof = rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))?;
+
+            let mut out = Vec::with_capacity(4 + proof.len() + vk.len());
+            out.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+            out.extend_from_slice(&proof);
+            out.extend_from_slice(&vk);
+            Ok(out)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Like `bb_prove_ultrahonk`, but the witness JSON is base64 gzipped like the
+/// bytecode, and subject to the same decompressed size limit.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_gz(
+    bytecode_b64_gz: *const c_char,
+    witness_json_b64_gz: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+
+            let witness_str = unsafe { cstr_to_string(witness_json_b64_gz) }?;
+            let witness_json = String::from_utf8(decode_b64_gz(&witness_str, "witness")?)
+                .map_err(|e| FfiError::decode(format!("Witness JSON is not UTF-8: {}", e)))?;
+
+            let settings = unsafe { parse_settings(settings_json) }?;
+            let prove_resp = prove_bytecode_with_str(bytecode, &witness_json, settings)?;
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Like `bb_prove_ultrahonk`, but takes the ACIR bytecode already decoded,
+/// skipping the base64 and gzip round trip.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_raw_bytecode(
+    bytecode_ptr: *const u8,
+    bytecode_len: usize,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode = unsafe { byte_slice(bytecode_ptr, bytecode_len) }?.to_vec();
+            let prove_resp = prove_bytecode(bytecode, witness_json, settings_json)?;
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Cheap sanity check of a caller-supplied UltraHonk VK: whole 32-byte fields
+/// and a header whose public input count covers the reserved inputs.
+fn check_vk(vk: &[u8], settings: &ProofSystemSettings) -> Result<(), FfiError> {
+    if vk.len() < 3 * 32 || vk.len() % 32 != 0 {
+        return Err(FfiError::invalid_argument(format!("Implausible verification key length {}", vk.len())));
+    }
+    vk_num_public_inputs(vk, settings).map_err(|e| FfiError::invalid_argument(e.message))?;
+    Ok(())
+}
+
+/// Like `bb_prove_ultrahonk`, but proves with the given VK instead of
+/// computing one, saving a backend round trip per proof. The VK must match the
+/// bytecode and settings; if the backend rejects it the error says so.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_with_vk(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let witness_bytes = unsafe { encode_witness_json(witness_json) }?;
+            let vk = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+            let settings = unsafe { parse_settings(settings_json) }?;
+            check_vk(&vk, &settings)?;
+
+            let prove_resp = prove(bytecode, vk, witness_bytes, settings).map_err(|e| match e.code {
+                ErrorCode::Backend => FfiError::backend(format!("Proving with the supplied verification key failed: {}", e)),
+                _ => e,
+            })?;
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct WitnessStackItemJson {
+    circuit_index: u32,
+    witness: WitnessValues,
+}
+
+/// Proves a program whose witness stack has several entries. `stack_json` is
+/// `[{"circuit_index": 0, "witness": [...]}, ...]`; the indices must be
+/// 0, 1, 2, ... in order.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_stack(
+    bytecode_b64_gz: *const c_char,
+    stack_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+
+            let stack_str = unsafe { cstr_to_string(stack_json) }?;
+            let parsed: Vec<WitnessStackItemJson> = serde_json::from_str(&stack_str).map_err(FfiError::invalid_argument)?;
+            if parsed.is_empty() {
+                return Err(FfiError::invalid_argument("Witness stack is empty"));
+            }
+            for (i, item) in parsed.iter().enumerate() {
+                if item.circuit_index as usize != i {
+                    return Err(FfiError::invalid_argument(format!(
+                        "Witness stack indices must be contiguous from 0: entry {} has circuit_index {}",
+                        i, item.circuit_index
+                    )));
+                }
+            }
+
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let witness_bytes = encode_witness_stack(parsed.into_iter().map(|item| (item.circuit_index, item.witness)).collect())?;
+            let vk = cached_vk(&bytecode, &settings)?;
+            let prove_resp = prove(bytecode, vk, witness_bytes, settings)?;
+
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(p) => ok(p),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Proves with a witness stack already encoded as `bb_encode_witness` returns
+/// it: the msgpack format byte (2) followed by the msgpack stack. The blob is
+/// passed to the backend as is, without parsing or range-checking the
+/// values; only the format byte and the main circuit's witness count are
+/// checked.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_witness_blob(
+    bytecode_b64_gz: *const c_char,
+    witness_blob_ptr: *const u8,
+    witness_blob_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+
+            let witness_bytes = unsafe { byte_slice(witness_blob_ptr, witness_blob_len) }?.to_vec();
+            match witness_bytes.first() {
+                Some(&WITNESS_STACK_FORMAT) if witness_bytes.len() > 1 => {}
+                Some(&format) if format != WITNESS_STACK_FORMAT => {
+                    return Err(FfiError::decode(format!(
+                        "Unsupported witness stack format {}, expected {} (msgpack)",
+                        format, WITNESS_STACK_FORMAT
+                    )))
+                }
+                _ => return Err(FfiError::decode("Empty witness stack")),
+            }
+
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            let prove_resp = prove(bytecode, vk, witness_bytes, settings)?;
+
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(p) => ok(p),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Proves with a witness in Noir's native `witness.gz` format, as written by
+/// `nargo execute`, base64 encoded. The decompressed witness stack is passed to
+/// the backend as is, without going through `WitnessJson`.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_from_witness_gz(
+    bytecode_b64_gz: *const c_char,
+    witness_gz_b64: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+
+            let witness_str = unsafe { cstr_to_string(witness_gz_b64) }?;
+            let witness_bytes = decode_b64_gz(&witness_str, "witness")
+                .map_err(|e| FfiError::decode(format!("Invalid witness.gz: {}", e)))?;
+
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            let prove_resp = prove(bytecode, vk, witness_bytes, settings)?;
+
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(p) => ok(p),
+            Err(e) => err(e),
+        }
+    })
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, FfiError> {
+    std::fs::read(path).map_err(|e| FfiError::invalid_argument(format!("{}: {}", path, e)))
+}
+
+/// Reads bytecode from a file: a `.json` file is a `nargo compile` artifact
+/// and its `"bytecode"` field is used; any other file holds the gzipped ACIR
+/// itself.
+fn read_bytecode_file(path: &str) -> Result<Vec<u8>, FfiError> {
+    #[derive(Deserialize)]
+    struct CompiledCircuit {
+        bytecode: String,
+    }
+
+    let data = read_file(path)?;
+    if path.ends_with(".json") {
+        let circuit: CompiledCircuit = serde_json::from_slice(&data)
+            .map_err(|e| FfiError::decode(format!("{}: not a nargo artifact: {}", path, e)))?;
+        decode_bytecode(&circuit.bytecode)
+    } else {
+        gunzip_bounded(&data)
+    }
+}
+
+/// Reads a witness from a file: `witness.gz` as written by `nargo execute`
+/// (any `.gz` file), or a `.json` file in the `bb_prove_ultrahonk` witness
+/// format.
+fn read_witness_file(path: &str) -> Result<Vec<u8>, FfiError> {
+    let data = read_file(path)?;
+    if path.ends_with(".gz") {
+        gunzip_bounded(&data).map_err(|e| FfiError::decode(format!("{}: invalid witness.gz: {}", path, e)))
+    } else if path.ends_with(".json") {
+        let wj_str = String::from_utf8(data).map_err(|e| FfiError::invalid_argument(format!("{}: {}", path, e)))?;
+        encode_witness_str(&wj_str)
+    } else {
+        Err(FfiError::invalid_argument(format!("{}: expected a .gz or .json witness", path)))
+    }
+}
+
+/// Like `bb_prove_ultrahonk`, but reads the bytecode and witness from disk,
+/// avoiding large strings across the FFI boundary. See `read_bytecode_file`
+/// and `read_witness_file` for the accepted formats.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_from_file(
+    bytecode_path: *const c_char,
+    witness_path: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_path = unsafe { cstr_to_string(bytecode_path) }?;
+            let bytecode = read_bytecode_file(&bytecode_path)?;
+            let witness_path = unsafe { cstr_to_string(witness_path) }?;
+            let witness_bytes = read_witness_file(&witness_path)?;
+
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            let prove_resp = prove(bytecode, vk, witness_bytes, settings)?;
+
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(p) => ok(p),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Like `bb_prove_ultrahonk`, but returns the proof in a flat binary framing
+/// instead of msgpack. All lengths are little-endian u32:
+///
+/// ```text
+/// proof_len: u32              length of the proof in bytes
+/// proof: proof_len bytes      proof field elements, 32 bytes each
+/// num_public_inputs: u32
+/// public_inputs: num_public_inputs * 32 bytes, big-endian field elements
+/// ```
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_raw(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = prove_ultrahonk(bytecode_b64_gz, witness_json, settings_json)?;
+
+            let proof = concat_fields(&prove_resp.proof);
+            let public_inputs = concat_fields(&prove_resp.public_inputs);
+
+            let mut out = Vec::with_capacity(8 + proof.len() + public_inputs.len());
+            out.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+            out.extend_from_slice(&proof);
+            out.extend_from_slice(&(prove_resp.public_inputs.len() as u32).to_le_bytes());
+            out.extend_from_slice(&public_inputs);
+            Ok(out)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct BatchProveItem {
+    proof: Option<CircuitProveResponse>,
+    error: Option<String>,
+}
+
+/// The bb API derives the proving key inside every prove call and has no
+/// command to export or import one, so this always fails with
+/// `BB_ERR_UNSUPPORTED`. The closest lever is reusing the VK, which
+/// `bb_prove_ultrahonk` caches and `bb_prove_ultrahonk_with_vk` accepts.
+#[no_mangle]
+pub extern "C" fn bb_compute_pk(_bytecode_b64_gz: *const c_char, _settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        err(FfiError::unsupported(
+            "The bb API does not expose proving keys; the proving key is derived inside each prove call",
+        ))
+    })
+}
+
+/// Counterpart of `bb_compute_pk`; always fails with `BB_ERR_UNSUPPORTED`.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_with_pk(
+    _bytecode_b64_gz: *const c_char,
+    _witness_json: *const c_char,
+    _pk_ptr: *const u8,
+    _pk_len: usize,
+    _settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        err(FfiError::unsupported(
+            "The bb API does not accept a precomputed proving key; use bb_prove_ultrahonk_with_vk to skip the VK",
+        ))
+    })
+}
+
+/// Proves one circuit against many witnesses, computing the VK once.
+/// `witnesses_json` is a JSON array of `{"witness": [...]}` objects. Returns a
+/// msgpack array with one `{proof, error}` map per witness, in input order;
+/// exactly one of the two fields is set, so a bad witness doesn't abort the
+/// rest of the batch.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_batch(
+    bytecode_b64_gz: *const c_char,
+    witnesses_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+
+            let wj_str = unsafe { cstr_to_string(witnesses_json) }?;
+            let parsed: Vec<WitnessJson> = serde_json::from_str(&wj_str).map_err(FfiError::invalid_argument)?;
+
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+
+            let items: Vec<BatchProveItem> = parsed
+                .into_iter()
+                .map(|wj| {
+                    let res = encode_witness(wj.witness)
+                        .and_then(|witness_bytes| prove(bytecode.clone(), vk.clone(), witness_bytes, settings.clone()));
+                    match res {
+                        Ok(proof) => BatchProveItem { proof: Some(proof), error: None },
+                        Err(e) => BatchProveItem { proof: None, error: Some(e.message) },
+                    }
+                })
+                .collect();
+
+            rmp_serde::to_vec_named(&items)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(p) => ok(p),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn bb_get_vk_ultrahonk(
+    bytecode_b64_gz: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+
+            // A cache hit is served before the settings are applied, which could
+            // need the backend, so it never waits for a proof in progress.
+            let settings_str = unsafe { settings_arg(settings_json) }?;
+            let resolved = resolve_settings(&settings_str)?;
+            let key = vk_cache_key(&bytecode, &resolved.settings)?;
+            if let Some(vk) = VK_CACHE.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+                return Ok(vk);
+            }
+
+            let settings = apply_settings(resolved)?;
+            cached_vk(&bytecode, &settings)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Decodes a msgpack `CircuitProveResponse`, rejecting buffers that aren't
+/// exactly one well-formed proof, e.g. truncated or padded by storage, with a
+/// `Decode` error so they can't pass for a proof that fails verification.
+fn decode_proof(proof_msgpack_ptr: *const u8, proof_msgpack_len: usize) -> Result<CircuitProveResponse, FfiError> {
+    let proof_msgpack = unsafe { byte_slice(proof_msgpack_ptr, proof_msgpack_len) }?;
+    decode_proof_bytes(proof_msgpack)
+}
+
+fn decode_proof_bytes(proof_msgpack: &[u8]) -> Result<CircuitProveResponse, FfiError> {
+    let corrupt = |what: String| FfiError::decode(format!("Corrupt proof buffer: {}", what));
+
+    let mut cursor = std::io::Cursor::new(proof_msgpack);
+    let prove_resp: CircuitProveResponse = Deserialize::deserialize(&mut rmp_serde::Deserializer::new(&mut cursor))
+        .map_err(|e| corrupt(format!("not a msgpack proof response ({})", e)))?;
+    if cursor.position() != proof_msgpack.len() as u64 {
+        return Err(corrupt(format!(
+            "{} trailing bytes after the proof response",
+            proof_msgpack.len() as u64 - cursor.position()
+        )));
+    }
+
+    check_proof(&prove_resp).map_err(corrupt)?;
+    Ok(prove_resp)
+}
+
+/// Shape checks shared by every decoded proof: a non-empty proof made of
+/// 32-byte field elements.
+fn check_proof(prove_resp: &CircuitProveResponse) -> Result<(), String> {
+    if prove_resp.proof.is_empty() {
+        return Err("empty proof".to_string());
+    }
+    let fields = prove_resp.proof.iter().chain(prove_resp.public_inputs.iter());
+    if let Some(field) = fields.map(AsRef::<[u8]>::as_ref).find(|f| f.len() != 32) {
+        return Err(format!("{}-byte field element", field.len()));
+    }
+    Ok(())
+}
+
+/// Checks that a buffer is a well-formed msgpack proof as returned by
+/// `bb_prove_ultrahonk`, without verifying it. Fails with `BB_ERR_DECODE` and
+/// a description of the damage otherwise.
+#[no_mangle]
+pub extern "C" fn bb_validate_proof_blob(proof_msgpack_ptr: *const u8, proof_msgpack_len: usize) -> BBResult {
+    ffi_boundary(|| {
+        match decode_proof(proof_msgpack_ptr, proof_msgpack_len) {
+            Ok(_) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Every sumcheck round of an UltraHonk proof carries a univariate of at least
+/// this many field elements.
+const MIN_SUMCHECK_ROUND_FIELDS: u64 = 8;
+
+#[derive(Serialize)]
+struct Compatibility {
+    compatible: bool,
+    reasons: Vec<String>,
+}
+
+/// Checks whether a proof and a VK could belong together, from their shapes
+/// alone and without running the verifier: the VK's header is well formed,
+/// its public input count (including the backend's reserved inputs) matches
+/// the proof's, and the proof is long enough for the VK's circuit size.
+/// Returns JSON `{"compatible", "reasons"}`, where `reasons` lists each
+/// mismatch. A compatible pair can still fail verification. A proof that
+/// can't be decoded fails with `BB_ERR_DECODE`.
+#[no_mangle]
+pub extern "C" fn bb_proof_vk_compatible(
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+            let vk = unsafe { byte_slice(vk_ptr, vk_len) }?;
+
+            // Header fields: log_circuit_size, num_public_inputs, pub_inputs_offset.
+            let header_field = |index: usize| -> Option<u64> {
+                let field = vk.get(index * 32..(index + 1) * 32)?;
+                field[..24].iter().all(|b| *b == 0).then(|| u64::from_be_bytes(field[24..].try_into().unwrap()))
+            };
+            let mut reasons = Vec::new();
+            if vk.len() < 3 * 32 || vk.len() % 32 != 0 {
+                reasons.push(format!("Implausible verification key length {}", vk.len()));
+            } else {
+                match header_field(0).filter(|log_n| (1..=32).contains(log_n)) {
+                    Some(log_n) => {
+                        let proof_fields = prove_resp.proof.len() as u64;
+                        if proof_fields < log_n * MIN_SUMCHECK_ROUND_FIELDS {
+                            reasons.push(format!(
+                                "Proof of {} fields is too short for a circuit of 2^{} gates",
+                                proof_fields, log_n
+                            ));
+                        }
+                    }
+                    None => reasons.push("Invalid circuit size in verification key".to_string()),
+                }
+                match header_field(1) {
+                    Some(vk_inputs) if vk_inputs != prove_resp.public_inputs.len() as u64 => reasons.push(format!(
+                        "Proof has {} public inputs, verification key expects {}",
+                        prove_resp.public_inputs.len(),
+                        vk_inputs
+                    )),
+                    Some(_) => {}
+                    None => reasons.push("Invalid public input count in verification key".to_string()),
+                }
+            }
+
+            serde_json::to_vec(&Compatibility { compatible: reasons.is_empty(), reasons }).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+fn concat_fields<F: AsRef<[u8]>>(fields: &[F]) -> Vec<u8> {
+    fields.iter().flat_map(|f| f.as_ref().iter().copied()).collect()
+}
+
+fn verify_ultrahonk(
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> Result<bool, FfiError> {
+    let vk_bytes = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+    
+    let settings = unsafe { parse_settings(settings_json) }?;
+
+    let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+
+    verify(vk_bytes, prove_resp, settings)
+}
+
+fn verify(vk: Vec<u8>, prove_resp: CircuitProveResponse, settings: ProofSystemSettings) -> Result<bool, FfiError> {
+    metrics::inc(&metrics::VERIFICATIONS_RUN);
+    let verified = match call_bb(Command::CircuitVerify(barretenberg_rs::generated_types::CircuitVerify::new(vk, prove_resp.public_inputs, prove_resp.proof, settings)))? {
+        barretenberg_rs::generated_types::Response::CircuitVerifyResponse(r) => r,
+        other => return Err(unexpected_response("CircuitVerifyResponse", &other)),
+    };
+
+    Ok(verified.verified)
+}
+
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk(
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> bool {
+    ffi_boundary_or(false, || {
+        verify_ultrahonk(proof_msgpack_ptr, proof_msgpack_len, vk_ptr, vk_len, settings_json).unwrap_or_else(|e| {
+            // Only `false` reaches the caller, so keep a trace of why.
+            logging::log(logging::LogLevel::Warn, format!("Verification could not run: {}", e));
+            false
+        })
+    })
+}
+
+/// Like `bb_verify_ultrahonk`, but reports why verification could not run.
+/// On success `ok` is true and the data buffer holds a single byte: 1 if the
+/// proof verified, 0 if it was rejected. `ok` is false only when verification
+/// itself failed (bad input, undecodable proof, backend error).
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk_ex(
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        match verify_ultrahonk(proof_msgpack_ptr, proof_msgpack_len, vk_ptr, vk_len, settings_json) {
+            Ok(verified) => ok(vec![verified as u8]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Verifies a proof against the VK of the given circuit, computed (or taken
+/// from the VK cache) from the bytecode, instead of a caller-supplied VK that
+/// might belong to another circuit. Reports like `bb_verify_ultrahonk_ex`.
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk_strict(
+    bytecode_b64_gz: *const c_char,
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<bool, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            verify(vk, prove_resp, settings)
+        })();
+
+        match res {
+            Ok(verified) => ok(vec![verified as u8]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Splits a msgpack array of proof responses into the encoded bytes of each
+/// element, so every proof can be decoded (and fail) on its own.
+fn split_proof_array(proofs_msgpack: &[u8]) -> Result<Vec<Vec<u8>>, FfiError> {
+    let mut cursor = std::io::Cursor::new(proofs_msgpack);
+    let count = rmp::decode::read_array_len(&mut cursor)
+        .map_err(|e| FfiError::decode(format!("Proofs are not a msgpack array: {}", e)))?;
+
+    let mut items = Vec::with_capacity((count as usize).min(proofs_msgpack.len()));
+    for i in 0..count {
+        let value = rmpv::decode::read_value(&mut cursor)
+            .map_err(|e| FfiError::decode(format!("Proof {} is not valid msgpack: {}", i, e)))?;
+        let mut item = Vec::new();
+        rmpv::encode::write_value(&mut item, &value)
+            .map_err(|e| FfiError::serialization(format!("Failed to re-encode proof {}: {}", i, e)))?;
+        items.push(item);
+    }
+    if cursor.position() != proofs_msgpack.len() as u64 {
+        return Err(FfiError::decode(format!(
+            "{} trailing bytes after the proofs array",
+            proofs_msgpack.len() as u64 - cursor.position()
+        )));
+    }
+    Ok(items)
+}
+
+/// Verifies many proofs against one VK, parsing the VK and settings once.
+/// `proofs_msgpack` is a msgpack array of `CircuitProveResponse`s, i.e. the
+/// buffers returned by `bb_prove_ultrahonk` behind an array header. Returns
+/// one byte per proof, in input order: 1 if it verified, 0 if it was
+/// rejected, 2 if it could not be verified (the reason is logged). Only an
+/// unreadable array, VK or settings fails the whole call.
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk_batch(
+    proofs_msgpack_ptr: *const u8,
+    proofs_msgpack_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let proofs_msgpack = unsafe { byte_slice(proofs_msgpack_ptr, proofs_msgpack_len) }?;
+            let vk_bytes = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+            let settings = unsafe { parse_settings(settings_json) }?;
+            check_vk(&vk_bytes, &settings)?;
+
+            let items = split_proof_array(proofs_msgpack)?;
+            let statuses = items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let res = decode_proof_bytes(item).and_then(|resp| verify(vk_bytes.clone(), resp, settings.clone()));
+                    match res {
+                        Ok(verified) => verified as u8,
+                        Err(e) => {
+                            logging::log(logging::LogLevel::Warn, format!("Proof {} could not be verified: {}", i, e));
+                            2
+                        }
+                    }
+                })
+                .collect();
+            Ok(statuses)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// A proof stored together with everything needed to verify it.
+#[derive(Serialize, Deserialize)]
+struct ProofBundle {
+    proof: CircuitProveResponse,
+    #[serde(with = "serde_bytes")]
+    vk: Vec<u8>,
+    settings: ProofSystemSettings,
+    /// Backend that produced the proof; absent from older bundles.
+    #[serde(default)]
+    producer: Option<ProducerVersion>,
+}
+
+/// Which backend build produced a proof, as far as it can be told.
+#[derive(Serialize, Deserialize)]
+struct ProducerVersion {
+    backend: String,
+    /// `bb --version` of the pipe backend; the native backend has none.
+    version: Option<String>,
+    ffi_version: String,
+}
+
+impl ProducerVersion {
+    fn current() -> Result<Self, FfiError> {
+        let info = backend_version()?;
+        Ok(ProducerVersion {
+            backend: info.backend.to_string(),
+            version: info.version,
+            ffi_version: info.ffi_version.to_string(),
+        })
+    }
+
+    /// Describes how `self` differs from `other`, or `None` if they match or
+    /// can't be compared: `bb` versions when both are known, otherwise the
+    /// library versions that statically link the native backend.
+    fn mismatch(&self, other: &ProducerVersion) -> Option<String> {
+        match (&self.version, &other.version) {
+            (Some(a), Some(b)) if a != b => Some(format!("produced by bb {}, verifying with bb {}", a, b)),
+            (None, None) if self.ffi_version != other.ffi_version => Some(format!(
+                "produced by the native backend of barretenberg_ffi {}, verifying with {}",
+                self.ffi_version, other.ffi_version
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Like `bb_prove_ultrahonk`, but returns a msgpack bundle
+/// `{proof, vk, settings, producer}` holding the proof response, the VK it
+/// was proved with, the resolved settings and the producing backend's
+/// version (`{backend, version, ffi_version}`), for `bb_verify_bundle`.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_bundle(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let witness_bytes = unsafe { encode_witness_json(witness_json) }?;
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            let proof = prove(bytecode, vk.clone(), witness_bytes, settings.clone())?;
+            let producer = ProducerVersion::current()
+                .map_err(|e| logging::log(logging::LogLevel::Warn, format!("Bundle without backend version: {}", e)))
+                .ok();
+
+            rmp_serde::to_vec_named(&ProofBundle { proof, vk, settings, producer })
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize bundle: {}", e)))
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Verifies a bundle from `bb_prove_ultrahonk_bundle` with the VK and
+/// settings it carries. Reports like `bb_verify_ultrahonk_ex`; a damaged
+/// bundle fails with `BB_ERR_DECODE`. Proofs only verify with the backend
+/// version that produced them, so a rejected bundle from another version
+/// fails with `BB_ERR_VERSION_MISMATCH` and a "version mismatch" message
+/// instead; an accepted one only logs a warning.
+#[no_mangle]
+pub extern "C" fn bb_verify_bundle(bundle_ptr: *const u8, bundle_len: usize) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<bool, FfiError> = (|| {
+            let bundle_bytes = unsafe { byte_slice(bundle_ptr, bundle_len) }?;
+            let corrupt = |what: String| FfiError::decode(format!("Corrupt proof bundle: {}", what));
+
+            let bundle: ProofBundle = rmp_serde::from_slice(bundle_bytes).map_err(|e| corrupt(e.to_string()))?;
+            check_proof(&bundle.proof).map_err(corrupt)?;
+            check_vk(&bundle.vk, &bundle.settings).map_err(|e| corrupt(e.message))?;
+
+            let verified = verify(bundle.vk, bundle.proof, bundle.settings)?;
+            let Some(producer) = bundle.producer else {
+                return Ok(verified);
+            };
+            let mismatch = match ProducerVersion::current() {
+                Ok(current) => producer.mismatch(&current),
+                Err(e) => {
+                    logging::log(logging::LogLevel::Debug, format!("Skipping the bundle version check: {}", e));
+                    None
+                }
+            };
+            match mismatch {
+                Some(mismatch) if !verified => {
+                    Err(FfiError::version_mismatch(format!("Proof bundle version mismatch: {}", mismatch)))
+                }
+                Some(mismatch) => {
+                    logging::log(logging::LogLevel::Warn, format!("Proof bundle version mismatch: {}", mismatch));
+                    Ok(verified)
+                }
+                None => Ok(verified),
+            }
+        })();
+
+        match res {
+            Ok(verified) => ok(vec![verified as u8]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Extracts the public inputs from a msgpack `CircuitProveResponse` without
+/// verifying it. Layout of the returned buffer:
+///
+/// ```text
+/// count: u32 little-endian
+/// count * 32 bytes: field elements, big-endian, in circuit order
+/// ```
+#[no_mangle]
+pub extern "C" fn bb_proof_public_inputs(proof_msgpack_ptr: *const u8, proof_msgpack_len: usize) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+            public_input_list(&prove_resp)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Encodes a proof's public inputs in the layout of `bb_proof_public_inputs`.
+fn public_input_list(prove_resp: &CircuitProveResponse) -> Result<Vec<u8>, FfiError> {
+    let mut out = Vec::with_capacity(4 + prove_resp.public_inputs.len() * 32);
+    out.extend_from_slice(&(prove_resp.public_inputs.len() as u32).to_le_bytes());
+    for (i, input) in prove_resp.public_inputs.iter().enumerate() {
+        if input.len() != 32 {
+            return Err(FfiError::decode(format!("Public input {} is {} bytes, expected 32", i, input.len())));
+        }
+        out.extend_from_slice(input.as_ref());
+    }
+    Ok(out)
+}
+
+/// Verifies a proof and returns the public inputs it verified with, in the
+/// layout of `bb_proof_public_inputs`, so callers act on exactly the inputs
+/// the verifier checked. A proof that doesn't verify fails with
+/// `BB_ERR_INVALID_ARGUMENT`; other failures report as usual.
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk_recover(
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let vk = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+            let settings = unsafe { parse_settings(settings_json) }?;
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+
+            // Encoded before verification consumes the response.
+            let public_inputs = public_input_list(&prove_resp)?;
+            if !verify(vk, prove_resp, settings)? {
+                return Err(FfiError::invalid_argument("Proof did not verify"));
+            }
+            Ok(public_inputs)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProofFields {
+    proof: String,
+    public_inputs: Vec<String>,
+}
+
+impl ProofFields {
+    fn from_response(prove_resp: &CircuitProveResponse) -> Self {
+        ProofFields {
+            proof: format!("0x{}", hex::encode(concat_fields(&prove_resp.proof))),
+            public_inputs: prove_resp
+                .public_inputs
+                .iter()
+                .map(|input| format!("0x{}", hex::encode(input)))
+                .collect(),
+        }
+    }
+}
+
+/// Splits a msgpack `CircuitProveResponse` into the raw proof and its public
+/// inputs, returned as JSON: `{"proof": "0x..", "public_inputs": ["0x..", ..]}`.
+/// `public_inputs` is an empty array for circuits without public inputs.
+#[no_mangle]
+pub extern "C" fn bb_proof_as_fields(proof_msgpack_ptr: *const u8, proof_msgpack_len: usize) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+            serde_json::to_vec(&ProofFields::from_response(&prove_resp)).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct HonkProofFields {
+    proof: Vec<String>,
+    public_inputs: Vec<String>,
+}
+
+/// Lays a proof out as the Solidity Honk verifier's `verify(bytes proof,
+/// bytes32[] publicInputs)` takes it, as JSON `{"proof": [..],
+/// "public_inputs": [..]}` of hex field elements; the `proof` fields,
+/// concatenated, are the `bytes` argument. `proof` starts with the
+/// `PAIRING_POINTS_SIZE` (16) pairing point fields the backend appends to
+/// the public inputs, followed by the proof fields in transcript order; its
+/// length is fixed for a given circuit size and ZK setting. `public_inputs`
+/// holds only the circuit's own public inputs. The Solidity verifier needs
+/// the Keccak oracle and has no IPA accumulation, so other settings fail
+/// with `BB_ERR_INVALID_ARGUMENT`.
+#[no_mangle]
+pub extern "C" fn bb_honk_proof_as_fields(
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+            // Only the flavor matters here, so the backend isn't touched.
+            let settings_str = unsafe { settings_arg(settings_json) }?;
+            let settings = resolve_settings(&settings_str)?.settings;
+
+            let (proof, public_inputs) = solidity_honk_fields(&prove_resp, &settings)?;
+            let to_hex = |f: &[u8]| format!("0x{}", hex::encode(f));
+            serde_json::to_vec(&HonkProofFields {
+                proof: proof.into_iter().map(to_hex).collect(),
+                public_inputs: public_inputs.into_iter().map(to_hex).collect(),
+            })
+            .map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Converts a decoded msgpack value into JSON, with binary values as `0x` hex
+/// strings. Only string map keys have a JSON form.
+fn msgpack_to_json(value: rmpv::Value) -> Result<serde_json::Value, FfiError> {
+    use rmpv::Value;
+    Ok(match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Boolean(b) => b.into(),
+        Value::Integer(i) => match (i.as_u64(), i.as_i64()) {
+            (Some(u), _) => u.into(),
+            (None, Some(n)) => n.into(),
+            (None, None) => return Err(FfiError::serialization(format!("Integer {} out of range", i))),
+        },
+        Value::F32(f) => (f as f64).into(),
+        Value::F64(f) => f.into(),
+        Value::String(s) => match s.into_str() {
+            Some(s) => s.into(),
+            None => return Err(FfiError::serialization("Proof holds a non-UTF-8 string")),
+        },
+        Value::Binary(bytes) => format!("0x{}", hex::encode(bytes)).into(),
+        Value::Array(items) => items.into_iter().map(msgpack_to_json).collect::<Result<Vec<_>, _>>()?.into(),
+        Value::Map(entries) => {
+            let mut object = serde_json::Map::with_capacity(entries.len());
+            for (key, value) in entries {
+                let key = match key {
+                    Value::String(s) => s.into_str(),
+                    _ => None,
+                }
+                .ok_or_else(|| FfiError::serialization("Proof holds a map with non-string keys"))?;
+                object.insert(key, msgpack_to_json(value)?);
+            }
+            object.into()
+        }
+        Value::Ext(..) => return Err(FfiError::serialization("Proof holds a msgpack extension value")),
+    })
+}
+
+/// Inverse of `msgpack_to_json`: `0x` strings become binary values.
+fn json_to_msgpack(value: serde_json::Value) -> Result<rmpv::Value, FfiError> {
+    use rmpv::Value;
+    Ok(match value {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(b) => Value::Boolean(b),
+        serde_json::Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+            (Some(u), _, _) => Value::from(u),
+            (None, Some(i), _) => Value::from(i),
+            (None, None, Some(f)) => Value::F64(f),
+            _ => return Err(FfiError::decode(format!("Unsupported number {}", n))),
+        },
+        serde_json::Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex_str) => Value::Binary(
+                hex::decode(hex_str).map_err(|e| FfiError::decode(format!("Invalid hex string {:?}: {}", s, e)))?,
+            ),
+            None => Value::from(s),
+        },
+        serde_json::Value::Array(items) => Value::Array(items.into_iter().map(json_to_msgpack).collect::<Result<_, _>>()?),
+        serde_json::Value::Object(object) => Value::Map(
+            object
+                .into_iter()
+                .map(|(k, v)| Ok((Value::from(k), json_to_msgpack(v)?)))
+                .collect::<Result<_, FfiError>>()?,
+        ),
+    })
+}
+
+/// Re-encodes a msgpack `CircuitProveResponse` as JSON with the same field
+/// names, byte fields as `0x` hex strings, e.g. `{"public_inputs": ["0x..",
+/// ..], "proof": ["0x..", ..]}`. `bb_proof_json_to_msgpack` reverses it.
+#[no_mangle]
+pub extern "C" fn bb_proof_msgpack_to_json(proof_msgpack_ptr: *const u8, proof_msgpack_len: usize) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+            let encoded = rmp_serde::to_vec_named(&prove_resp).map_err(FfiError::serialization)?;
+            let value = rmpv::decode::read_value(&mut encoded.as_slice()).map_err(FfiError::serialization)?;
+            serde_json::to_vec(&msgpack_to_json(value)?).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Turns the JSON form of `bb_proof_msgpack_to_json` back into the msgpack
+/// proof `bb_verify_ultrahonk` takes. Any JSON string starting with `0x` is
+/// read as bytes. The result is checked like `bb_validate_proof_blob`.
+#[no_mangle]
+pub extern "C" fn bb_proof_json_to_msgpack(proof_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let proof_str = unsafe { cstr_to_string(proof_json) }?;
+            let json: serde_json::Value = serde_json::from_str(&proof_str).map_err(FfiError::invalid_argument)?;
+            let mut encoded = Vec::new();
+            rmpv::encode::write_value(&mut encoded, &json_to_msgpack(json)?).map_err(FfiError::serialization)?;
+
+            let prove_resp = decode_proof_bytes(&encoded)?;
+            rmp_serde::to_vec_named(&prove_resp).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Splits a proof into the `proof` and `publicInputs` fields of the Solidity
+/// Honk verifier, as described at `bb_honk_proof_as_fields`.
+fn solidity_honk_fields<'a>(
+    prove_resp: &'a CircuitProveResponse,
+    settings: &ProofSystemSettings,
+) -> Result<(Vec<&'a [u8]>, Vec<&'a [u8]>), FfiError> {
+    if settings.oracle_hash_type != "keccak" {
+        return Err(FfiError::invalid_argument(format!(
+            "The Solidity verifier needs the keccak oracle hash, not {}",
+            settings.oracle_hash_type
+        )));
+    }
+    if settings.ipa_accumulation {
+        return Err(FfiError::invalid_argument("The Solidity verifier does not support IPA accumulation"));
+    }
+
+    let mut public_inputs: Vec<&[u8]> = prove_resp.public_inputs.iter().map(AsRef::<[u8]>::as_ref).collect();
+    let reserved = reserved_public_inputs(settings) as usize;
+    if public_inputs.len() < reserved {
+        return Err(FfiError::decode(format!(
+            "Proof has {} public inputs, expected at least {} for the pairing points",
+            public_inputs.len(),
+            reserved
+        )));
+    }
+    let mut proof = public_inputs.split_off(public_inputs.len() - reserved);
+    proof.extend(prove_resp.proof.iter().map(AsRef::<[u8]>::as_ref));
+    Ok((proof, public_inputs))
+}
+
+/// ABI encoding of the arguments `(bytes proof, bytes32[] publicInputs)` of
+/// the Solidity Honk verifier's `verify`: two offsets, then each argument's
+/// length followed by its 32-byte words.
+fn abi_encode_honk_args(proof: &[&[u8]], public_inputs: &[&[u8]]) -> Vec<u8> {
+    let word = |n: usize| {
+        let mut w = [0u8; 32];
+        w[24..].copy_from_slice(&(n as u64).to_be_bytes());
+        w
+    };
+    let proof_offset = 2 * 32;
+    let inputs_offset = proof_offset + 32 + proof.len() * 32;
+
+    let mut out = Vec::with_capacity(inputs_offset + 32 + public_inputs.len() * 32);
+    out.extend_from_slice(&word(proof_offset));
+    out.extend_from_slice(&word(inputs_offset));
+    out.extend_from_slice(&word(proof.len() * 32));
+    proof.iter().for_each(|f| out.extend_from_slice(f));
+    out.extend_from_slice(&word(public_inputs.len()));
+    public_inputs.iter().for_each(|f| out.extend_from_slice(f));
+    out
+}
+
+const VERIFIERS: [&str; 2] = ["solidity_honk", "solidity_plonk"];
+
+/// Frames a proof as the calldata of an on-chain verifier, after the function
+/// selector. `verifier` is one of:
+///
+/// - `"solidity_honk"`: the ABI-encoded arguments `(bytes proof, bytes32[]
+///   publicInputs)` of the verifier from `bb_write_solidity_verifier`, laid
+///   out as by `bb_honk_proof_as_fields` (Keccak oracle, no IPA
+///   accumulation; ZK or not as in the settings the proof was made with).
+/// - `"solidity_plonk"`: fails with `BB_ERR_UNSUPPORTED`, the backend has no
+///   Plonk prover.
+#[no_mangle]
+pub extern "C" fn bb_proof_for_verifier(
+    proof_msgpack_ptr: *const u8,
+    proof_msgpack_len: usize,
+    verifier: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = decode_proof(proof_msgpack_ptr, proof_msgpack_len)?;
+            let verifier = unsafe { cstr_to_string(verifier) }?;
+            let settings_str = unsafe { settings_arg(settings_json) }?;
+            let settings = resolve_settings(&settings_str)?.settings;
+
+            match verifier.as_str() {
+                "solidity_honk" => {
+                    let (proof, public_inputs) = solidity_honk_fields(&prove_resp, &settings)?;
+                    Ok(abi_encode_honk_args(&proof, &public_inputs))
+                }
+                "solidity_plonk" => Err(FfiError::unsupported("Plonk proofs are not supported by this backend")),
+                other => Err(FfiError::invalid_argument(format!(
+                    "Unknown verifier {:?}, expected one of {:?}",
+                    other, VERIFIERS
+                ))),
+            }
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Like `bb_prove_ultrahonk`, but returns the proof in the ASCII-safe form of
+/// `bb_proof_as_fields`: `{"proof": "0x..", "public_inputs": ["0x..", ..]}`.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_hex(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = prove_ultrahonk(bytecode_b64_gz, witness_json, settings_json)?;
+            serde_json::to_vec(&ProofFields::from_response(&prove_resp)).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Converts 32-byte field elements into the field type the backend commands
+/// take.
+fn to_backend_fields<T: TryFrom<Vec<u8>>>(fields: Vec<[u8; 32]>) -> Result<Vec<T>, FfiError> {
+    fields
+        .into_iter()
+        .map(|f| T::try_from(f.to_vec()).map_err(|_| FfiError::decode("Invalid field element")))
+        .collect()
+}
+
+/// Splits concatenated 32-byte field elements; `what` names the buffer in the
+/// error.
+fn split_fields(bytes: &[u8], what: &str) -> Result<Vec<[u8; 32]>, FfiError> {
+    if bytes.len() % 32 != 0 {
+        return Err(FfiError::decode(format!("{} length {} is not a multiple of 32 bytes", what, bytes.len())));
+    }
+    Ok(bytes.chunks(32).map(|c| c.try_into().unwrap()).collect())
+}
+
+/// Verifies a proof given as separate field elements rather than a
+/// `CircuitProveResponse`.
+fn verify_fields(
+    vk: Vec<u8>,
+    public_inputs: Vec<[u8; 32]>,
+    proof: Vec<[u8; 32]>,
+    settings: ProofSystemSettings,
+) -> Result<bool, FfiError> {
+    metrics::inc(&metrics::VERIFICATIONS_RUN);
+    match call_bb(Command::CircuitVerify(barretenberg_rs::generated_types::CircuitVerify::new(
+        vk,
+        to_backend_fields(public_inputs)?,
+        to_backend_fields(proof)?,
+        settings,
+    )))? {
+        barretenberg_rs::generated_types::Response::CircuitVerifyResponse(r) => Ok(r.verified),
+        other => Err(unexpected_response("CircuitVerifyResponse", &other)),
+    }
+}
+
+/// Verifies a proof in the JSON form returned by `bb_prove_ultrahonk_hex`.
+/// The result is reported as by `bb_verify_ultrahonk_ex`.
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk_hex(
+    proof_json: *const c_char,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<bool, FfiError> = (|| {
+            let proof_str = unsafe { cstr_to_string(proof_json) }?;
+            let fields: ProofFields = serde_json::from_str(&proof_str).map_err(FfiError::invalid_argument)?;
+            let vk = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let proof_hex = fields.proof.strip_prefix("0x").unwrap_or(&fields.proof);
+            let proof = split_fields(&hex::decode(proof_hex).map_err(FfiError::decode)?, "Proof")?;
+            let public_inputs = fields
+                .public_inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| {
+                    parse_field(input, false).map_err(|e| {
+                        FfiError::decode(format!("Failed to parse public_inputs[{}]: {}", i, e)).with_context("public_input_index", i)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            verify_fields(vk, public_inputs, proof, settings)
+        })();
+
+        match res {
+            Ok(verified) => ok(vec![verified as u8]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Generates a Solidity verifier contract for the given VK. Only the Keccak
+/// oracle hash has an EVM verifier, so other settings are rejected. Returns the
+/// `.sol` source as UTF-8.
+#[no_mangle]
+pub extern "C" fn bb_write_solidity_verifier(
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let vk_bytes = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            if settings.oracle_hash_type != "keccak" {
+                return Err(FfiError::invalid_argument(format!(
+                    "No Solidity verifier for oracle hash type {:?}, use \"keccak\"",
+                    settings.oracle_hash_type
+                )));
+            }
+
+            match call_bb(Command::CircuitWriteSolidityVerifier(barretenberg_rs::generated_types::CircuitWriteSolidityVerifier::new(vk_bytes, settings)))? {
+                barretenberg_rs::generated_types::Response::CircuitWriteSolidityVerifierResponse(r) => Ok(r.solidity_code.into_bytes()),
+                other => Err(unexpected_response("CircuitWriteSolidityVerifierResponse", &other)),
+            }
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct CircuitInfo {
+    num_gates: u64,
+    subgroup_size: u64,
+    num_acir_opcodes: u64,
+    num_public_inputs: u64,
+}
+
+/// Returns the size of a circuit without proving it, as JSON:
+/// `{"num_gates", "subgroup_size", "num_acir_opcodes", "num_public_inputs"}`.
+/// `subgroup_size` is the gate count rounded up to a power of two, which is
+/// also the number of SRS points the proof needs.
+#[no_mangle]
+pub extern "C" fn bb_circuit_info(
+    bytecode_b64_gz: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let stats = circuit_stats(bytecode.clone(), settings.clone())?;
+            let vk = cached_vk(&bytecode, &settings)?;
+
+            let info = CircuitInfo {
+                num_gates: stats.num_gates as u64,
+                subgroup_size: stats.num_gates_dyadic as u64,
+                num_acir_opcodes: stats.num_acir_opcodes as u64,
+                num_public_inputs: vk_num_public_inputs(&vk, &settings)?,
+            };
+            serde_json::to_vec(&info).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Name and payload of a serialized enum value: a map with the variant as its
+/// single key, or just the variant name for unit variants.
+fn acir_variant(value: &rmpv::Value) -> Option<(String, Option<&rmpv::Value>)> {
+    match value {
+        rmpv::Value::String(name) => Some((name.as_str()?.to_string(), None)),
+        rmpv::Value::Map(entries) if entries.len() == 1 => {
+            let (key, payload) = &entries[0];
+            let name = match key.as_str() {
+                Some(name) => name.to_string(),
+                None => format!("variant_{}", key.as_u64()?),
+            };
+            Some((name, Some(payload)))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Serialize)]
+struct AcirFunctionStats {
+    name: String,
+    opcodes: u64,
+    /// Opcode count per kind: `AssertZero`, `BlackBoxFuncCall`, `MemoryOp`, ...
+    kinds: BTreeMap<String, u64>,
+    /// `BlackBoxFuncCall` count per function: `RANGE`, `AND`, ...
+    black_box_calls: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize)]
+struct AcirStats {
+    functions: Vec<AcirFunctionStats>,
+}
+
+/// Counts the opcodes of each function in an ACIR program, by kind and, for
+/// black box calls, by black box function, as JSON `{"functions": [{"name",
+/// "opcodes", "kinds": {..}, "black_box_calls": {..}}]}` with the main
+/// function first. Reads the bytecode only, without the backend, so it is
+/// cheap but says nothing about gate counts (see `bb_circuit_info`). Only
+/// msgpack-serialized programs are understood.
+#[no_mangle]
+pub extern "C" fn bb_acir_stats(bytecode_b64_gz: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let unreadable = |what: &str| FfiError::decode(format!("Unsupported ACIR program: {}", what));
+
+            let program = acir_program(&bytecode).ok_or_else(|| unreadable("not msgpack-serialized"))?;
+            let functions = acir_field(&program, "functions", 0)
+                .and_then(rmpv::Value::as_array)
+                .ok_or_else(|| unreadable("no functions"))?;
+
+            let mut stats = Vec::with_capacity(functions.len());
+            for (i, function) in functions.iter().enumerate() {
+                // Circuit fields in order: function_name, current_witness_index, opcodes, ...
+                let name = acir_field(function, "function_name", 0)
+                    .and_then(rmpv::Value::as_str)
+                    .map_or_else(|| format!("function_{}", i), str::to_string);
+                let opcodes = acir_field(function, "opcodes", 2)
+                    .and_then(rmpv::Value::as_array)
+                    .ok_or_else(|| unreadable(&format!("no opcodes in function {}", i)))?;
+
+                let mut function_stats = AcirFunctionStats {
+                    name,
+                    opcodes: opcodes.len() as u64,
+                    kinds: BTreeMap::new(),
+                    black_box_calls: BTreeMap::new(),
+                };
+                for opcode in opcodes {
+                    let (kind, payload) = acir_variant(opcode).ok_or_else(|| unreadable("malformed opcode"))?;
+                    if kind == "BlackBoxFuncCall" {
+                        let call = payload.and_then(acir_variant).map_or_else(|| "unknown".to_string(), |(name, _)| name);
+                        *function_stats.black_box_calls.entry(call).or_default() += 1;
+                    }
+                    *function_stats.kinds.entry(kind).or_default() += 1;
+                }
+                stats.push(function_stats);
+            }
+            serde_json::to_vec(&AcirStats { functions: stats }).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Rough prover memory per row of the subgroup: the UltraHonk prover keeps on
+/// the order of 80 polynomials of 32-byte field elements (selectors, wires,
+/// permutation and lookup polynomials, their shifts and scratch space), plus
+/// 64 bytes of SRS per point.
+const PROVER_BYTES_PER_ROW: u64 = 3 * 1024;
+
+/// Memory the backend needs regardless of circuit size.
+const PROVER_BASE_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Serialize)]
+struct MemoryEstimate {
+    bytes: u64,
+    subgroup_size: u64,
+}
+
+/// Estimates the peak memory of proving a circuit, as JSON `{"bytes",
+/// "subgroup_size"}`. It is a heuristic linear in the subgroup size, meant
+/// for placing jobs, and only asks the backend for the circuit size.
+#[no_mangle]
+pub extern "C" fn bb_estimate_memory(bytecode_b64_gz: *const c_char, settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let subgroup_size = circuit_stats(bytecode, settings)?.num_gates_dyadic as u64;
+            let estimate = MemoryEstimate {
+                bytes: PROVER_BASE_BYTES.saturating_add(subgroup_size.saturating_mul(PROVER_BYTES_PER_ROW)),
+                subgroup_size,
+            };
+            serde_json::to_vec(&estimate).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct RecursiveProof {
+    proof: Vec<String>,
+    public_inputs: Vec<String>,
+    aggregation_object: Vec<String>,
+    vk: Vec<String>,
+}
+
+/// Proves an inner circuit for verification inside an outer Noir circuit.
+/// Recursive verification needs the Poseidon2 transcript, so the oracle hash
+/// is forced to it. Returns JSON with every value as a hex field element:
+/// `proof`, the circuit's own `public_inputs`, the `aggregation_object` the
+/// backend appends to them (pairing points, plus the IPA claim with
+/// `ipa_accumulation`), and the `vk` as fields.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_recursive(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+
+            let wj_str = unsafe { cstr_to_string(witness_json) }?;
+            let parsed: WitnessJson = serde_json::from_str(&wj_str).map_err(FfiError::invalid_argument)?;
+
+            let mut settings = unsafe { parse_settings(settings_json) }?;
+            settings.oracle_hash_type = "poseidon2".to_string();
+            let reserved = reserved_public_inputs(&settings) as usize;
+
+            let witness_bytes = encode_witness(parsed.witness)?;
+            let vk = cached_vk(&bytecode, &settings)?;
+            let vk_fields = vk_as_fields(vk.clone())?;
+            let prove_resp = prove(bytecode, vk, witness_bytes, settings)?;
+
+            let to_hex = |f: &[u8]| format!("0x{}", hex::encode(f));
+            let mut public_inputs: Vec<String> = prove_resp.public_inputs.iter().map(|f| to_hex(f.as_ref())).collect();
+            if public_inputs.len() < reserved {
+                return Err(FfiError::backend(format!(
+                    "Proof has {} public inputs, expected at least {} for the aggregation object",
+                    public_inputs.len(),
+                    reserved
+                )));
+            }
+            let aggregation_object = public_inputs.split_off(public_inputs.len() - reserved);
+
+            let recursive = RecursiveProof {
+                proof: prove_resp.proof.iter().map(|f| to_hex(f.as_ref())).collect(),
+                public_inputs,
+                aggregation_object,
+                vk: vk_fields,
+            };
+            serde_json::to_vec(&recursive).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Returns the VK as a JSON array of hex field elements, the form in which an
+/// outer circuit takes it as input.
+#[no_mangle]
+pub extern "C" fn bb_vk_as_fields(vk_ptr: *const u8, vk_len: usize) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let vk_bytes = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+            let fields = vk_as_fields(vk_bytes)?;
+            serde_json::to_vec(&fields).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Computes the full VK response for a circuit, seeding the VK cache with its
+/// bytes on the way.
+fn compute_vk_from_bytecode(
+    bytecode_b64_gz: *const c_char,
+    settings_json: *const c_char,
+) -> Result<CircuitComputeVkResponse, FfiError> {
+    let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+    let bytecode = decode_bytecode(&bytecode_str)?;
+    let settings = unsafe { parse_settings(settings_json) }?;
+
+    let key = vk_cache_key(&bytecode, &settings)?;
+    let vk = compute_vk(bytecode, settings)?;
+    VK_CACHE.write().unwrap_or_else(|e| e.into_inner()).insert(key, vk.bytes.clone());
+    Ok(vk)
+}
+
+/// Returns the 32-byte hash of the circuit's VK as computed by `bb`, which
+/// follows the settings' oracle hash (Poseidon2 unless Keccak is selected).
+/// This is the `vk_hash` that `bb write_vk` writes, the value to register
+/// on-chain or to pin in a recursive circuit. This is the canonical name;
+/// `bb_vk_hash_ultrahonk` is an alias.
+#[no_mangle]
+pub extern "C" fn bb_vk_hash(bytecode_b64_gz: *const c_char, settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        match compute_vk_from_bytecode(bytecode_b64_gz, settings_json) {
+            Ok(vk) => ok(vk.hash),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Alias of `bb_vk_hash` under the name of the UltraHonk family, with the
+/// same behaviour. New code should call `bb_vk_hash`.
+#[no_mangle]
+pub extern "C" fn bb_vk_hash_ultrahonk(bytecode_b64_gz: *const c_char, settings_json: *const c_char) -> BBResult {
+    bb_vk_hash(bytecode_b64_gz, settings_json)
+}
+
+/// Returns the circuit's VK as a JSON array of hex field elements, like
+/// `bb_vk_as_fields` but starting from the bytecode.
+#[no_mangle]
+pub extern "C" fn bb_vk_fields(bytecode_b64_gz: *const c_char, settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let vk = compute_vk_from_bytecode(bytecode_b64_gz, settings_json)?;
+            let fields: Vec<String> = vk.fields.iter().map(|f| format!("0x{}", hex::encode(f))).collect();
+            serde_json::to_vec(&fields).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct BackendVersion {
+    backend: &'static str,
+    configured: String,
+    bb_path: Option<String>,
+    version: Option<String>,
+    ffi_version: &'static str,
+}
+
+/// Reports which backend is in use and its version, as JSON:
+/// `{"backend", "configured", "bb_path", "version", "ffi_version"}`.
+/// `backend` is the one actually running, which differs from the
+/// `configured` type after a fallback from native. For the pipe backend
+/// `version` is the output of `bb --version`; the statically linked native
+/// backend has no version query, so `bb_path` and `version` are null there.
+#[no_mangle]
+pub extern "C" fn bb_backend_version() -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let info = backend_version()?;
+            serde_json::to_vec(&info).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+fn backend_version() -> Result<BackendVersion, FfiError> {
+    let backend = get_api()?;
+
+    let info = match backend.api {
+        #[cfg(unix)]
+        ApiEnum::Pipe(_) => {
+            let bb_path = match backend.config.bb_path.clone() {
+                Some(p) => p,
+                None => find_bb_binary()?,
+            };
+            let version = bb_binary_version(&bb_path)?;
+            BackendVersion {
+                backend: "pipe",
+                configured: backend.config.backend_type.clone(),
+                bb_path: Some(bb_path),
+                version: Some(version),
+                ffi_version: env!("CARGO_PKG_VERSION"),
+            }
+        }
+        #[cfg(feature = "native-backend")]
+        ApiEnum::Native(_) => BackendVersion {
+            backend: "native",
+            configured: backend.config.backend_type.clone(),
+            bb_path: None,
+            version: None,
+            ffi_version: env!("CARGO_PKG_VERSION"),
+        },
+    };
+    Ok(info)
+}
+
+#[derive(Serialize)]
+struct Capabilities {
+    backends: Vec<&'static str>,
+    proof_systems: Vec<&'static str>,
+    solidity_verifier: bool,
+}
+
+/// Reports what this build supports, without initializing the backend, as
+/// JSON: `{"backends", "proof_systems", "solidity_verifier"}`.
+#[no_mangle]
+pub extern "C" fn bb_capabilities() -> BBResult {
+    ffi_boundary(|| {
+        #[allow(unused_mut)]
+        let mut backends = vec!["pipe"];
+        #[cfg(feature = "native-backend")]
+        backends.push("native");
+
+        let capabilities = Capabilities {
+            backends,
+            proof_systems: vec!["ultra_honk"],
+            solidity_verifier: true,
+        };
+
+        match serde_json::to_vec(&capabilities) {
+            Ok(v) => ok(v),
+            Err(e) => err(FfiError::serialization(e)),
+        }
+    })
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+const WORKER_THREADS: usize = 2;
+
+/// Background workers running async jobs. Backend calls still serialize on
+/// the backend mutex; the pool only keeps that wait off the caller's thread.
+static WORKERS: Lazy<std::sync::Mutex<mpsc::Sender<Job>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<Job>();
+    let rx = Arc::new(std::sync::Mutex::new(rx));
+    for _ in 0..WORKER_THREADS {
+        let rx = rx.clone();
+        std::thread::spawn(move || loop {
+            let job = match rx.lock().unwrap_or_else(|e| e.into_inner()).recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            };
+            // Jobs catch their own panics; this only keeps the worker alive
+            // and leaves no per-call state behind for the next job.
+            ffi_boundary_or((), job);
+        });
+    }
+    std::sync::Mutex::new(tx)
+});
+
+/// Queues `job` on the worker pool. Should the workers be gone, the job runs
+/// on the caller's thread instead, so it always runs exactly once.
+fn spawn_job(job: impl FnOnce() + Send + 'static) {
+    let sent = WORKERS.lock().unwrap_or_else(|e| e.into_inner()).send(Box::new(job));
+    if let Err(mpsc::SendError(job)) = sent {
+        job();
+    }
+}
+
+/// Callback receiving the result of an async call. The callee owns `result`
+/// and must release it with `bb_free_bytes` / `bb_free_err`.
+pub type BBResultCallback = extern "C" fn(user_data: *mut c_void, result: BBResult);
+
+/// Opaque caller pointer handed back to the callback untouched.
+struct UserData(*mut c_void);
+
+unsafe impl Send for UserData {}
+
+fn copy_cstr(p: *const c_char) -> Option<CString> {
+    if p.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(p) }.to_owned())
+    }
+}
+
+fn cstr_ptr(s: &Option<CString>) -> *const c_char {
+    s.as_ref().map_or(std::ptr::null(), |s| s.as_ptr())
+}
+
+/// Asynchronous `bb_prove_ultrahonk`. The arguments are copied before this
+/// returns, so the caller may free them right away. `callback` is invoked
+/// exactly once, normally from a background thread, with `user_data` and the
+/// result, which it then owns.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_async(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+    callback: BBResultCallback,
+    user_data: *mut c_void,
+) {
+    ffi_boundary_or((), || {
+        let bytecode = copy_cstr(bytecode_b64_gz);
+        let witness = copy_cstr(witness_json);
+        let settings = copy_cstr(settings_json);
+        let user_data = UserData(user_data);
+
+        spawn_job(move || {
+            let user_data = user_data;
+            let res = bb_prove_ultrahonk(cstr_ptr(&bytecode), cstr_ptr(&witness), cstr_ptr(&settings));
+            callback(user_data.0, res);
+        });
+    })
+}
+
+/// A proof started with `bb_prove_begin`; `result` stays `None` while it runs.
+struct ProveJob {
+    watch: Arc<CallWatch>,
+    created: std::time::Instant,
+    /// Set once a worker picks the job up; until then it is queued.
+    started: AtomicBool,
+    result: std::sync::Mutex<Option<Result<Vec<u8>, FfiError>>>,
+}
+
+impl ProveJob {
+    fn new() -> Self {
+        ProveJob {
+            watch: Arc::default(),
+            created: std::time::Instant::now(),
+            started: AtomicBool::new(false),
+            result: std::sync::Mutex::new(None),
+        }
+    }
+
+    fn state(&self) -> &'static str {
+        if self.result.lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+            "completed"
+        } else if self.started.load(Ordering::SeqCst) {
+            "running"
+        } else {
+            "queued"
+        }
+    }
+}
+
+static JOBS: Lazy<std::sync::Mutex<HashMap<u64, Arc<ProveJob>>>> = Lazy::new(Default::default);
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+fn lookup_job(jobs: &HashMap<u64, Arc<ProveJob>>, job_id: u64) -> Result<Arc<ProveJob>, FfiError> {
+    jobs.get(&job_id)
+        .cloned()
+        .ok_or_else(|| FfiError::invalid_argument(format!("Unknown job id {}", job_id)))
+}
+
+/// Starts `bb_prove_ultrahonk` in the background and returns its job id,
+/// never 0 unless the library failed internally. The arguments are copied
+/// before this returns, and any errors in them surface from `bb_prove_poll`.
+#[no_mangle]
+pub extern "C" fn bb_prove_begin(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> u64 {
+    ffi_boundary_or(0, || {
+        let bytecode = copy_cstr(bytecode_b64_gz);
+        let witness = copy_cstr(witness_json);
+        let settings = copy_cstr(settings_json);
+
+        let job_id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+        let job = Arc::new(ProveJob::new());
+        JOBS.lock().unwrap_or_else(|e| e.into_inner()).insert(job_id, job.clone());
+
+        spawn_job(move || {
+            job.started.store(true, Ordering::SeqCst);
+            JOB_WATCH.with(|w| *w.borrow_mut() = Some(job.watch.clone()));
+            let res = catch_panic(|| prove_ultrahonk_msgpack(cstr_ptr(&bytecode), cstr_ptr(&witness), cstr_ptr(&settings)))
+                .and_then(|res| res);
+            JOB_WATCH.with(|w| *w.borrow_mut() = None);
+            *job.result.lock().unwrap_or_else(|e| e.into_inner()) = Some(res);
+        });
+        job_id
+    })
+}
+
+/// Checks on a job. While it runs this fails with `BB_ERR_PENDING`; once it
+/// has finished, returns what `bb_prove_ultrahonk` would have and releases
+/// the job id.
+#[no_mangle]
+pub extern "C" fn bb_prove_poll(job_id: u64) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+            let job = lookup_job(&jobs, job_id)?;
+            let result = job.result.lock().unwrap_or_else(|e| e.into_inner()).take();
+            match result {
+                Some(result) => {
+                    jobs.remove(&job_id);
+                    result
+                }
+                None => Err(FfiError::pending(format!("Job {} is still running", job_id))),
+            }
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Cancels a job and releases its id. A pipe backend call in progress is
+/// aborted by killing its `bb` subprocess, which the next call respawns; a
+/// native backend call cannot be interrupted, so cancelling fails with
+/// `BB_ERR_CANCELLED` while one runs.
+#[no_mangle]
+pub extern "C" fn bb_prove_cancel(job_id: u64) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+            let job = lookup_job(&jobs, job_id)?;
+            job.watch.abandon(Abandoned::Cancelled)?;
+            jobs.remove(&job_id);
+            Ok(vec![])
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct JobStatus {
+    id: u64,
+    state: &'static str,
+    age_ms: u64,
+}
+
+#[derive(Serialize)]
+struct JobsStatus {
+    queued: usize,
+    running: usize,
+    completed: usize,
+    jobs: Vec<JobStatus>,
+}
+
+/// Lists the jobs started with `bb_prove_begin` that have not been released
+/// yet, as JSON `{"queued", "running", "completed", "jobs"}` where each job is
+/// `{"id", "state", "age_ms"}`, ordered by id. Completed jobs stay listed
+/// until polled. Leaves the jobs untouched.
+#[no_mangle]
+pub extern "C" fn bb_jobs_status() -> BBResult {
+    ffi_boundary(|| {
+        let mut jobs: Vec<JobStatus> = JOBS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(&id, job)| JobStatus {
+                id,
+                state: job.state(),
+                age_ms: job.created.elapsed().as_millis() as u64,
+            })
+            .collect();
+        jobs.sort_by_key(|j| j.id);
+
+        let count = |state: &str| jobs.iter().filter(|j| j.state == state).count();
+        let status = JobsStatus {
+            queued: count("queued"),
+            running: count("running"),
+            completed: count("completed"),
+            jobs,
+        };
+        match serde_json::to_vec(&status) {
+            Ok(v) => ok(v),
+            Err(e) => err(FfiError::serialization(e)),
+        }
+    })
+}
+
+/// Turns proving latency measurements on or off; off by default.
+#[no_mangle]
+pub extern "C" fn bb_enable_timing(enabled: bool) {
+    timing::ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns the timing of the last proof completed while timing was enabled,
+/// as JSON `{"vk_ms", "prove_ms", "total_ms"}`, or `null` if there is none.
+/// For a batch, `total_ms` runs from the start of the batch.
+#[no_mangle]
+pub extern "C" fn bb_last_timing() -> BBResult {
+    ffi_boundary(|| {
+        match serde_json::to_vec(&timing::last()) {
+            Ok(v) => ok(v),
+            Err(e) => err(FfiError::serialization(e)),
+        }
+    })
+}
+
+/// Installs `callback` to receive log lines, or removes it when null. Levels
+/// are `BB_LOG_ERROR` (0), `BB_LOG_WARN` (1), `BB_LOG_INFO` (2) and
+/// `BB_LOG_DEBUG` (3). Besides the crate's own events, the stderr of `bb`
+/// subprocesses is forwarded line by line at debug level.
+/// The callback may be invoked from any thread.
+#[no_mangle]
+pub extern "C" fn bb_set_log_callback(callback: Option<logging::LogCallback>) {
+    ffi_boundary_or((), || {
+        logging::set_callback(callback);
+    })
+}
+
+/// Installs `callback` to receive the phases of UltraHonk proofs, or removes
+/// it when null: `BB_PHASE_COMPUTING_VK` (0, only on a VK cache miss),
+/// `BB_PHASE_PROVING` (1) and `BB_PHASE_DONE` (2), each with a rough
+/// percentage. There is no progress within a phase. The callback runs on the
+/// thread making the proof, so it may be invoked from any thread.
+#[no_mangle]
+pub extern "C" fn bb_set_progress_callback(callback: Option<progress::ProgressCallback>) {
+    ffi_boundary_or((), || {
+        progress::set_callback(callback);
+    })
+}
+
+/// Returns the counters as JSON: `proofs_started`, `proofs_succeeded`,
+/// `proofs_failed`, `vks_computed`, `verifications_run`, `backend_calls` and
+/// `backend_restarts`. Proofs whose inputs fail to decode never start; VK
+/// cache hits don't count as computed.
+#[no_mangle]
+pub extern "C" fn bb_metrics_json() -> BBResult {
+    ffi_boundary(|| {
+        match serde_json::to_vec(&metrics::snapshot()) {
+            Ok(v) => ok(v),
+            Err(e) => err(FfiError::serialization(e)),
+        }
+    })
+}
+
+/// `nargo compile` output of `testdata/circuit`, embedded by the build script;
+/// empty when it wasn't compiled before building.
+const SELF_TEST_CIRCUIT: &str = include_str!(concat!(env!("OUT_DIR"), "/self_test_circuit.json"));
+
+/// Decoded bytecode of the embedded test circuit and a satisfying witness.
+/// Commands for it are sent under `SELF_TEST_CIRCUIT_NAME`.
+fn self_test_inputs() -> Result<(Vec<u8>, Vec<u8>), FfiError> {
+    #[derive(Deserialize)]
+    struct CompiledCircuit {
+        bytecode: String,
+    }
+
+    if SELF_TEST_CIRCUIT.is_empty() {
+        return Err(FfiError::invalid_argument(
+            "Self test unavailable: library built without the compiled test circuit",
+        ));
+    }
+    let circuit: CompiledCircuit = serde_json::from_str(SELF_TEST_CIRCUIT).map_err(FfiError::decode)?;
+    let bytecode = decode_bytecode(&circuit.bytecode)?;
+    // x * x == y with x = 3, y = 9.
+    let witness_bytes = encode_witness(WitnessValues::Dense(vec!["0x03".to_string(), "0x09".to_string()]))?;
+    Ok((bytecode, witness_bytes))
+}
+
+#[derive(Serialize)]
+struct SelfTestTiming {
+    vk_ms: u64,
+    prove_ms: u64,
+    verify_ms: u64,
+    total_ms: u64,
+}
+
+/// Health check: computes the VK of a tiny embedded circuit, proves it and
+/// verifies the proof, exercising the backend, the SRS and the prover and
+/// verifier end to end. Returns JSON timing `{"vk_ms", "prove_ms",
+/// "verify_ms", "total_ms"}`, or the first error.
+#[no_mangle]
+pub extern "C" fn bb_self_test() -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let (bytecode, witness_bytes) = self_test_inputs()?;
+            let settings = default_settings();
+            set_circuit_name(Some(SELF_TEST_CIRCUIT_NAME.to_string()));
+
+            let started = std::time::Instant::now();
+            let (vk, vk_ms) = timing::measure(|| compute_vk(bytecode.clone(), settings.clone()));
+            let vk = vk?.bytes;
+            let (proof, prove_ms) = timing::measure(|| prove(bytecode, vk.clone(), witness_bytes, settings.clone()));
+            let proof = proof?;
+            let (verified, verify_ms) = timing::measure(|| verify(vk, proof, settings));
+            if !verified? {
+                return Err(FfiError::backend("Self test proof did not verify"));
+            }
+
+            let total_ms = started.elapsed().as_millis() as u64;
+            let timing = SelfTestTiming { vk_ms, prove_ms, verify_ms, total_ms };
+            serde_json::to_vec(&timing).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
 
-        let circuit_input_no_vk = CircuitInputNoVK {
-            name: "circuit".to_string(),
-            bytecode: bytecode.clone(), 
-        };
+#[derive(Serialize)]
+struct WarmupTiming {
+    spawn_ms: u64,
+    round_trip_ms: u64,
+    total_ms: u64,
+}
 
-        let vk_resp = match call_bb(Command::CircuitComputeVk(barretenberg_rs::generated_types::CircuitComputeVk::new(circuit_input_no_vk, settings.clone())))? {
-            barretenberg_rs::generated_types::Response::CircuitComputeVkResponse(r) => r,
-            _ => return Err("Unexpected response".to_string()),
-        };
+/// Starts the backend ahead of the first proof: initializes it (spawning `bb`
+/// for the pipe backend) and makes one cheap round trip, querying the size of
+/// the embedded test circuit, so it is known to answer. The round trip is
+/// skipped (0 ms) in builds without the test circuit. Returns JSON timing
+/// `{"spawn_ms", "round_trip_ms", "total_ms"}`.
+#[no_mangle]
+pub extern "C" fn bb_warmup() -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let started = std::time::Instant::now();
+            let (api, spawn_ms) = timing::measure(get_api);
+            drop(api?);
 
-        let circuit_input = CircuitInput {
-            name: "circuit".to_string(),
-            bytecode,
-            verification_key: vk_resp.bytes,
-        };
+            let mut round_trip_ms = 0;
+            if !SELF_TEST_CIRCUIT.is_empty() {
+                let (bytecode, _) = self_test_inputs()?;
+                let (stats, ms) = timing::measure(|| {
+                    with_circuit_name(SELF_TEST_CIRCUIT_NAME, || circuit_stats(bytecode, default_settings()))
+                });
+                stats?;
+                round_trip_ms = ms;
+            }
+
+            let total_ms = started.elapsed().as_millis() as u64;
+            serde_json::to_vec(&WarmupTiming { spawn_ms, round_trip_ms, total_ms }).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+fn compute_mega_vk(bytecode: Vec<u8>) -> Result<Vec<u8>, FfiError> {
+    let circuit = CircuitInputNoVK {
+        name: DEFAULT_CIRCUIT_NAME.to_string(),
+        bytecode,
+    };
+
+    metrics::inc(&metrics::VKS_COMPUTED);
+    match call_bb(Command::ChonkComputeStandaloneVk(
+        barretenberg_rs::generated_types::ChonkComputeStandaloneVk::new(circuit),
+    ))? {
+        barretenberg_rs::generated_types::Response::ChonkComputeStandaloneVkResponse(r) => Ok(r.bytes),
+        other => Err(unexpected_response("ChonkComputeStandaloneVkResponse", &other)),
+    }
+}
+
+/// Computes the MegaHonk VK of a circuit, as used for the circuits of a
+/// ClientIVC stack. Returns the raw VK bytes.
+#[no_mangle]
+pub extern "C" fn bb_get_vk_megahonk(bytecode_b64_gz: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            compute_mega_vk(bytecode)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// MegaHonk circuits are only proven as part of a ClientIVC stack; `bb` has
+/// no command to prove one on its own, so this always fails with
+/// `BB_ERR_UNSUPPORTED`. The signature mirrors `bb_prove_ultrahonk`.
+#[no_mangle]
+pub extern "C" fn bb_prove_megahonk(
+    _bytecode_b64_gz: *const c_char,
+    _witness_json: *const c_char,
+    _settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        err(FfiError::unsupported(
+            "Standalone MegaHonk proving is not supported by the bb API; prove through the ClientIVC flow",
+        ))
+    })
+}
+
+/// Counterpart of `bb_prove_megahonk`; always fails with `BB_ERR_UNSUPPORTED`.
+#[no_mangle]
+pub extern "C" fn bb_verify_megahonk(
+    _proof_ptr: *const u8,
+    _proof_len: usize,
+    _vk_ptr: *const u8,
+    _vk_len: usize,
+) -> BBResult {
+    ffi_boundary(|| {
+        err(FfiError::unsupported(
+            "Standalone MegaHonk verification is not supported by the bb API; verify the ClientIVC proof instead",
+        ))
+    })
+}
+
+/// Error message of the Plonk entry points.
+const PLONK_UNSUPPORTED: &str = "UltraPlonk is not supported by this bb version; use UltraHonk, or an older bb for Plonk";
 
-        let prove_resp = match call_bb(Command::CircuitProve(barretenberg_rs::generated_types::CircuitProve::new(circuit_input, witness_bytes, settings)))? {
-            barretenberg_rs::generated_types::Response::CircuitProveResponse(r) => r,
-            _ => return Err("Unexpected response".to_string()),
+/// UltraPlonk was removed from `bb`, whose API no longer has Plonk commands,
+/// so this always fails with `BB_ERR_UNSUPPORTED`. Plonk proofs need an older
+/// `bb` driven directly. The signature mirrors `bb_prove_ultrahonk`.
+#[no_mangle]
+pub extern "C" fn bb_prove_plonk(
+    _bytecode_b64_gz: *const c_char,
+    _witness_json: *const c_char,
+    _settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| err(FfiError::unsupported(PLONK_UNSUPPORTED)))
+}
+
+/// Counterpart of `bb_prove_plonk`; always fails with `BB_ERR_UNSUPPORTED`.
+#[no_mangle]
+pub extern "C" fn bb_get_vk_plonk(_bytecode_b64_gz: *const c_char, _settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| err(FfiError::unsupported(PLONK_UNSUPPORTED)))
+}
+
+/// Counterpart of `bb_prove_plonk`; always fails with `BB_ERR_UNSUPPORTED`.
+#[no_mangle]
+pub extern "C" fn bb_verify_plonk(
+    _proof_ptr: *const u8,
+    _proof_len: usize,
+    _vk_ptr: *const u8,
+    _vk_len: usize,
+    _settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| err(FfiError::unsupported(PLONK_UNSUPPORTED)))
+}
+
+#[derive(Serialize)]
+struct ProofSystemSupport {
+    name: &'static str,
+    prove: bool,
+    verify: bool,
+}
+
+#[derive(Serialize)]
+struct SupportedFlavors {
+    oracle_hashes: &'static [&'static str],
+    proof_systems: Vec<ProofSystemSupport>,
+    zk: bool,
+}
+
+/// Lists the configurations this library can drive, as JSON:
+/// `{"oracle_hashes": [..], "proof_systems": [{"name", "prove", "verify"}],
+/// "zk"}`. MegaHonk only proves inside ClientIVC (`"client_ivc"`) and the bb
+/// API no longer has UltraPlonk, so both are listed as unsupported rather
+/// than left out. Known from the bb API this library is built against;
+/// doesn't start a backend.
+#[no_mangle]
+pub extern "C" fn bb_supported_flavors() -> BBResult {
+    ffi_boundary(|| {
+        let system = |name, prove, verify| ProofSystemSupport { name, prove, verify };
+        let flavors = SupportedFlavors {
+            oracle_hashes: &ORACLE_HASH_TYPES,
+            proof_systems: vec![
+                system("ultra_honk", true, true),
+                system("client_ivc", true, false),
+                system("mega_honk", false, false),
+                system("plonk", false, false),
+            ],
+            zk: true,
         };
+        match serde_json::to_vec(&flavors) {
+            Ok(v) => ok(v),
+            Err(e) => err(FfiError::serialization(e)),
+        }
+    })
+}
 
-        let resp_bytes = rmp_serde::to_vec_named(&prove_resp)
-            .map_err(|e| format!("Failed to serialize response: {}", e))?;
-        
-        Ok(resp_bytes)
-    })();
+#[derive(Deserialize)]
+struct CivcSettings {
+    num_circuits: u32,
+}
 
-    match res {
-        Ok(p) => ok(p),
-        Err(e) => err(e),
+/// Starts a ClientIVC session that folds `num_circuits` circuit executions
+/// into one proof. `settings_json` is `{"num_circuits": n}`. Replaces any
+/// session in progress. The session lives in the backend, so it is lost, and
+/// later session calls fail, if the backend is respawned or reset.
+#[no_mangle]
+pub extern "C" fn bb_civc_start(settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let settings_str = unsafe { cstr_to_string(settings_json) }?;
+            let settings: CivcSettings = serde_json::from_str(&settings_str).map_err(FfiError::invalid_argument)?;
+            if settings.num_circuits == 0 {
+                return Err(FfiError::invalid_argument("\"num_circuits\" must be positive"));
+            }
+
+            match call_bb(Command::ChonkStart(barretenberg_rs::generated_types::ChonkStart::new(settings.num_circuits)))? {
+                barretenberg_rs::generated_types::Response::ChonkStartResponse(_) => {}
+                other => return Err(unexpected_response("ChonkStartResponse", &other)),
+            }
+            get_api()?.civc = Some(CivcSession { num_circuits: settings.num_circuits, accumulated: 0 });
+            Ok(())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Checks that a ClientIVC session is open and returns how many circuits it
+/// expects and has accumulated so far.
+fn civc_progress() -> Result<(u32, u32), FfiError> {
+    let backend = get_api()?;
+    let session = backend
+        .civc
+        .as_ref()
+        .ok_or_else(|| FfiError::invalid_argument("No ClientIVC session in progress; call bb_civc_start first"))?;
+    Ok((session.num_circuits, session.accumulated))
+}
+
+/// Accumulates one circuit execution into the session: loads the circuit
+/// with its MegaHonk VK, then folds in the witness (same JSON format as
+/// `bb_prove_ultrahonk`).
+#[no_mangle]
+pub extern "C" fn bb_civc_accumulate(bytecode_b64_gz: *const c_char, witness_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let (num_circuits, accumulated) = civc_progress()?;
+            if accumulated >= num_circuits {
+                return Err(FfiError::invalid_argument(format!(
+                    "ClientIVC session already holds all {} circuits",
+                    num_circuits
+                )));
+            }
+
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let witness_bytes = unsafe { encode_witness_json(witness_json) }?;
+
+            let vk = compute_mega_vk(bytecode.clone())?;
+            let circuit = CircuitInput {
+                name: format!("circuit_{}", accumulated),
+                bytecode,
+                verification_key: vk,
+            };
+            match call_bb(Command::ChonkLoad(barretenberg_rs::generated_types::ChonkLoad::new(circuit)))? {
+                barretenberg_rs::generated_types::Response::ChonkLoadResponse(_) => {}
+                other => return Err(unexpected_response("ChonkLoadResponse", &other)),
+            }
+            match call_bb(Command::ChonkAccumulate(barretenberg_rs::generated_types::ChonkAccumulate::new(witness_bytes)))? {
+                barretenberg_rs::generated_types::Response::ChonkAccumulateResponse(_) => {}
+                other => return Err(unexpected_response("ChonkAccumulateResponse", &other)),
+            }
+
+            let mut backend = get_api()?;
+            let session = backend
+                .civc
+                .as_mut()
+                .ok_or_else(|| FfiError::backend("ClientIVC session lost while accumulating"))?;
+            session.accumulated += 1;
+            Ok(())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Produces the ClientIVC proof once every circuit has been accumulated and
+/// closes the session. Returns the msgpack-encoded proof response.
+#[no_mangle]
+pub extern "C" fn bb_civc_prove() -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let (num_circuits, accumulated) = civc_progress()?;
+            if accumulated < num_circuits {
+                return Err(FfiError::invalid_argument(format!(
+                    "ClientIVC session has {} of {} circuits accumulated",
+                    accumulated, num_circuits
+                )));
+            }
+
+            metrics::inc(&metrics::PROOFS_STARTED);
+            let resp = match call_bb(Command::ChonkProve(barretenberg_rs::generated_types::ChonkProve::new())) {
+                Ok(barretenberg_rs::generated_types::Response::ChonkProveResponse(r)) => r,
+                Ok(other) => {
+                    metrics::inc(&metrics::PROOFS_FAILED);
+                    return Err(unexpected_response("ChonkProveResponse", &other));
+                }
+                Err(e) => {
+                    metrics::inc(&metrics::PROOFS_FAILED);
+                    return Err(e);
+                }
+            };
+            metrics::inc(&metrics::PROOFS_SUCCEEDED);
+            get_api()?.civc = None;
+
+            rmp_serde::to_vec_named(&resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+fn verify_ultrahonk_parts(
+    proof_ptr: *const u8,
+    proof_len: usize,
+    public_inputs_ptr: *const u8,
+    public_inputs_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> Result<bool, FfiError> {
+    let proof = split_fields(unsafe { byte_slice(proof_ptr, proof_len) }?, "Proof")?;
+    // A circuit without public inputs may pass a null pointer with length 0.
+    let public_inputs = if public_inputs_len == 0 {
+        vec![]
+    } else {
+        split_fields(unsafe { byte_slice(public_inputs_ptr, public_inputs_len) }?, "Public inputs")?
+    };
+    let vk = unsafe { byte_slice(vk_ptr, vk_len) }?.to_vec();
+    let settings = unsafe { parse_settings(settings_json) }?;
+
+    verify_fields(vk, public_inputs, proof, settings)
+}
+
+/// Verifies a proof from its components instead of a msgpack
+/// `CircuitProveResponse`: the raw proof bytes and the public inputs, both as
+/// concatenated 32-byte big-endian field elements (the layout of
+/// `bb_prove_ultrahonk_raw`). Returns false on any error.
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk_parts(
+    proof_ptr: *const u8,
+    proof_len: usize,
+    public_inputs_ptr: *const u8,
+    public_inputs_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> bool {
+    ffi_boundary_or(false, || {
+        verify_ultrahonk_parts(
+            proof_ptr,
+            proof_len,
+            public_inputs_ptr,
+            public_inputs_len,
+            vk_ptr,
+            vk_len,
+            settings_json,
+        )
+        .unwrap_or(false)
+    })
+}
+
+/// Like `bb_verify_ultrahonk_parts`, with errors reported as by
+/// `bb_verify_ultrahonk_ex`.
+#[no_mangle]
+pub extern "C" fn bb_verify_ultrahonk_parts_ex(
+    proof_ptr: *const u8,
+    proof_len: usize,
+    public_inputs_ptr: *const u8,
+    public_inputs_len: usize,
+    vk_ptr: *const u8,
+    vk_len: usize,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        match verify_ultrahonk_parts(
+            proof_ptr,
+            proof_len,
+            public_inputs_ptr,
+            public_inputs_len,
+            vk_ptr,
+            vk_len,
+            settings_json,
+        ) {
+            Ok(verified) => ok(vec![verified as u8]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Identifies the backend a persisted VK was computed with: its type and the
+/// `bb` version (the library version for the native backend).
+fn vk_cache_backend_tag() -> Result<String, FfiError> {
+    let info = backend_version()?;
+    Ok(format!("{} {}", info.backend, info.version.as_deref().unwrap_or(info.ffi_version)))
+}
+
+fn vk_cache_io_error(path: &str, e: std::io::Error) -> FfiError {
+    let message = format!("{}: {}", path, e);
+    match e.kind() {
+        std::io::ErrorKind::InvalidData => FfiError::decode(message),
+        _ => FfiError::invalid_argument(message),
     }
 }
 
+/// Saves the VK cache to `path`, so a later process can `bb_vk_cache_load`
+/// it instead of recomputing the VKs.
 #[no_mangle]
-pub extern "C" fn bb_get_vk_ultrahonk(
+pub extern "C" fn bb_vk_cache_save(path: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let path = unsafe { cstr_to_string(path) }?;
+            let tag = vk_cache_backend_tag()?;
+            VK_CACHE
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .save(std::path::Path::new(&path), &tag)
+                .map_err(|e| vk_cache_io_error(&path, e))
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Loads VKs saved by `bb_vk_cache_save` into the cache. Files written with a
+/// different backend or `bb` version are ignored, so stale VKs are never
+/// used. Returns the number of VKs loaded as a little-endian u32.
+#[no_mangle]
+pub extern "C" fn bb_vk_cache_load(path: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let path = unsafe { cstr_to_string(path) }?;
+            let tag = vk_cache_backend_tag()?;
+            let loaded = VK_CACHE
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .load(std::path::Path::new(&path), &tag)
+                .map_err(|e| vk_cache_io_error(&path, e))?;
+            Ok((loaded as u32).to_le_bytes().to_vec())
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Size of the buffer handed to a `BBWitnessCallback`, enough for any field
+/// element in hex or signed decimal plus the terminating nul.
+const WITNESS_VALUE_BUF_LEN: usize = 80;
+
+/// Writes the value of witness `index` into `out_value` as a nul-terminated
+/// string in any format accepted in witness JSON.
+pub type BBWitnessCallback = extern "C" fn(user_data: *mut c_void, index: u32, out_value: *mut c_char);
+
+/// Encodes a dense witness of `len` values pulled one at a time from `next`,
+/// writing the same msgpack as `encode_witness` directly, so only the
+/// encoded buffer is ever held in memory.
+fn encode_witness_streamed(len: u32, mut next: impl FnMut(u32) -> Result<String, FfiError>) -> Result<Vec<u8>, FfiError> {
+    let range_check = FIELD_RANGE_CHECK.load(Ordering::Relaxed);
+    let ser_err = |e: rmp::encode::ValueWriteError| FfiError::serialization(format!("Failed to serialize witness stack: {}", e));
+
+    // A single witness at circuit index 0: [[[0, {index: bin32, ...}]]].
+    let mut buf = Vec::with_capacity(16 + len as usize * 40);
+    buf.push(WITNESS_STACK_FORMAT);
+    rmp::encode::write_array_len(&mut buf, 1).map_err(ser_err)?;
+    rmp::encode::write_array_len(&mut buf, 1).map_err(ser_err)?;
+    rmp::encode::write_array_len(&mut buf, 2).map_err(ser_err)?;
+    rmp::encode::write_uint(&mut buf, 0).map_err(ser_err)?;
+    rmp::encode::write_map_len(&mut buf, len).map_err(ser_err)?;
+    for i in 0..len {
+        let field_bytes = witness_field(i, &next(i)?, range_check)?;
+        rmp::encode::write_uint(&mut buf, i as u64).map_err(ser_err)?;
+        rmp::encode::write_bin(&mut buf, &field_bytes).map_err(ser_err)?;
+    }
+    Ok(buf)
+}
+
+/// Like `bb_prove_ultrahonk`, but pulls the `witness_len` witness values from
+/// `callback` one at a time instead of parsing a JSON array, keeping memory
+/// flat for very large witnesses. The callback is called with indices 0 to
+/// `witness_len - 1` in order on the calling thread, and gets a zeroed
+/// buffer of `BB_WITNESS_VALUE_BUF_LEN` bytes to write into.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_witness_stream(
     bytecode_b64_gz: *const c_char,
+    witness_len: u32,
+    callback: BBWitnessCallback,
+    user_data: *mut c_void,
     settings_json: *const c_char,
 ) -> BBResult {
-    let res = (|| {
-        let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
-        let bytecode = decode_bytecode(&bytecode_str)?;
-        
-        let settings_str = unsafe { cstr_to_string(settings_json) }?;
-        let settings: ProofSystemSettings = serde_json::from_str(&settings_str).map_err(|e| e.to_string())?;
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            timing::start();
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let settings = unsafe { parse_settings(settings_json) }?;
 
-        let circuit_input = CircuitInputNoVK {
-            name: "circuit".to_string(),
-            bytecode,
-        };
+            let mut value_buf = [0u8; WITNESS_VALUE_BUF_LEN];
+            let witness_bytes = encode_witness_streamed(witness_len, |i| {
+                value_buf.fill(0);
+                callback(user_data, i, value_buf.as_mut_ptr() as *mut c_char);
+                // Never trust the callback to have terminated the string.
+                value_buf[WITNESS_VALUE_BUF_LEN - 1] = 0;
+                CStr::from_bytes_until_nul(&value_buf)
+                    .ok()
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_owned)
+                    .ok_or_else(|| FfiError::invalid_argument(format!("Witness value {} is not valid UTF-8", i)))
+            })?;
 
-        let vk_resp = match call_bb(Command::CircuitComputeVk(barretenberg_rs::generated_types::CircuitComputeVk::new(circuit_input, settings)))? {
-            barretenberg_rs::generated_types::Response::CircuitComputeVkResponse(r) => r,
-            _ => return Err("Unexpected response".to_string()),
-        };
-            
-        Ok(vk_resp.bytes)
-    })();
+            let vk = cached_vk(&bytecode, &settings)?;
+            let prove_resp = prove(bytecode, vk, witness_bytes, settings)?;
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
 
-    match res {
-        Ok(v) => ok(v),
-        Err(e) => err(e),
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Largest chunk handed to a `BBChunkCallback`.
+const PROOF_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Receives `len` bytes of output at `data`, valid only during the call.
+/// `len == 0` marks the end of the output.
+pub type BBChunkCallback = extern "C" fn(user_data: *mut c_void, data: *const u8, len: usize);
+
+/// Like `bb_prove_ultrahonk`, but hands the msgpack proof to `callback` in
+/// order, in chunks of at most `BB_PROOF_CHUNK_SIZE` bytes, instead of
+/// returning it in a `BBResult`. The whole proof is still built and
+/// serialized first; the chunks are fixed slices of it, delivered on the
+/// calling thread once proving is done and followed by one call with
+/// `len == 0`. The result then has no data. On error the callback isn't
+/// called at all.
+#[no_mangle]
+pub extern "C" fn bb_prove_ultrahonk_chunked(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+    callback: BBChunkCallback,
+    user_data: *mut c_void,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let prove_resp = prove_ultrahonk(bytecode_b64_gz, witness_json, settings_json)?;
+            rmp_serde::to_vec_named(&prove_resp)
+                .map_err(|e| FfiError::serialization(format!("Failed to serialize response: {}", e)))
+        })();
+
+        match res {
+            Ok(proof) => {
+                for chunk in proof.chunks(PROOF_CHUNK_SIZE) {
+                    callback(user_data, chunk.as_ptr(), chunk.len());
+                }
+                callback(user_data, std::ptr::null(), 0);
+                ok(vec![])
+            }
+            Err(e) => err(e),
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct SettingsValidation {
+    settings: serde_json::Value,
+    warnings: Vec<String>,
+}
+
+/// Resolves `settings_json` the way every other call does, without touching
+/// the backend, and returns `{"settings": {...}, "warnings": [...]}`: the
+/// complete settings that would be used, and a warning for each key that
+/// took its default or was ignored as unknown, e.g. a misspelled field.
+/// Invalid settings fail with `BB_ERR_INVALID_ARGUMENT`.
+#[no_mangle]
+pub extern "C" fn bb_validate_settings(settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let settings_str = unsafe { settings_arg(settings_json) }?;
+            let resolved = resolve_settings(&settings_str)?;
+
+            let mut settings = serde_json::to_value(&resolved.settings).map_err(FfiError::serialization)?;
+            if let Some(obj) = settings.as_object_mut() {
+                if let Some(threads) = resolved.threads {
+                    obj.insert("threads".to_string(), threads.into());
+                }
+                if let Some(name) = resolved.circuit_name {
+                    obj.insert("circuit_name".to_string(), name.into());
+                }
+            }
+            let warnings = resolved
+                .defaulted
+                .iter()
+                .map(|key| format!("{:?} not set, using the default", key))
+                .chain(resolved.unknown.iter().map(|key| format!("Unknown key {:?} ignored", key)))
+                .collect();
+
+            serde_json::to_vec(&SettingsValidation { settings, warnings }).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Proof length in bytes per bytecode/settings pair, recorded by `prove`.
+/// The backend has no query for it and the proof layout (field count per
+/// sumcheck round, commitments, padding) differs between flavors and `bb`
+/// versions, so it can't be derived from the VK.
+static PROOF_LENGTHS: Lazy<std::sync::Mutex<HashMap<vk_cache::CacheKey, usize>>> = Lazy::new(Default::default);
+
+/// Length in bytes of the circuit's proofs under `settings`. On a miss the
+/// circuit is proven once with an all-zero witness: the length depends only
+/// on the circuit and settings, and the prover doesn't check that the
+/// witness satisfies the circuit.
+fn proof_len(bytecode: &[u8], vk: &[u8], settings: &ProofSystemSettings) -> Result<usize, FfiError> {
+    let key = vk_cache_key(bytecode, settings)?;
+    if let Some(len) = PROOF_LENGTHS.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        return Ok(*len);
     }
+
+    let witness_count = acir_witness_count(bytecode)
+        .ok_or_else(|| FfiError::invalid_argument("Can't read the circuit's witness count from the bytecode"))?;
+    let witness_bytes = encode_witness_streamed(witness_count, |_| Ok("0".to_string()))?;
+    Ok(prove(bytecode.to_vec(), vk.to_vec(), witness_bytes, settings.clone())?.proof.len() * 32)
+}
+
+#[derive(Serialize)]
+struct ProofSizes {
+    proof_bytes: usize,
+    vk_bytes: usize,
+    public_inputs_count: u64,
 }
 
+/// Returns JSON `{"proof_bytes", "vk_bytes", "public_inputs_count"}` for the
+/// circuit under `settings_json`, for budgeting calldata before proving.
+/// `proof_bytes` excludes the public inputs, and `public_inputs_count` counts
+/// the circuit's own inputs, not those the backend reserves. Computes (or
+/// reuses) the VK. `proof_bytes` is taken from an earlier proof of the
+/// circuit under the same settings; the first time, the circuit is proven
+/// with an all-zero witness to measure it, which costs one prove.
 #[no_mangle]
-pub extern "C" fn bb_verify_ultrahonk(
+pub extern "C" fn bb_proof_sizes(bytecode_b64_gz: *const c_char, settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            let sizes = ProofSizes {
+                proof_bytes: proof_len(&bytecode, &vk, &settings)?,
+                vk_bytes: vk.len(),
+                public_inputs_count: vk_num_public_inputs(&vk, &settings)?,
+            };
+            serde_json::to_vec(&sizes).map_err(FfiError::serialization)
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// An independent backend, created by `bb_backend_new`. Each handle owns its
+/// own `bb` subprocess (or native context) and lock, so calls on different
+/// handles run in parallel. The VK cache, metrics and global settings such as
+/// the call timeout are shared with the global backend.
+///
+/// Handles are never deallocated: `bb_backend_free` shuts the backend down
+/// and empties the slot, but the slot's memory stays, so a call racing with
+/// the free still refers to valid memory and fails cleanly. Only the empty
+/// slot, a few hundred bytes, outlives each handle.
+pub struct BBHandle {
+    slot: BackendSlot,
+}
+
+/// Addresses of the handles that haven't been freed.
+static LIVE_HANDLES: Lazy<std::sync::Mutex<std::collections::HashSet<usize>>> = Lazy::new(Default::default);
+
+#[derive(Deserialize)]
+struct HandleConfigJson {
+    #[serde(default)]
+    backend_type: Option<String>,
+    #[serde(default)]
+    bb_path: Option<String>,
+    #[serde(default)]
+    threads: Option<usize>,
+}
+
+/// Creates a backend handle from JSON `{"backend_type", "bb_path",
+/// "threads"}`, all optional: the type defaults to `BB_BACKEND_TYPE` (else
+/// native), the path to the usual lookup and the threads to the CPU count.
+/// Returns null on failure, reporting the error to the log callback. Free with
+/// `bb_backend_free`.
+#[no_mangle]
+pub extern "C" fn bb_backend_new(config_json: *const c_char) -> *mut BBHandle {
+    ffi_boundary_or(null_mut(), || {
+        let res: Result<BBHandle, FfiError> = (|| {
+            let config_str = unsafe { cstr_to_string(config_json) }?;
+            let config: HandleConfigJson = serde_json::from_str(&config_str).map_err(FfiError::invalid_argument)?;
+            let config = BackendConfig {
+                backend_type: config
+                    .backend_type
+                    .or_else(|| std::env::var("BB_BACKEND_TYPE").ok())
+                    .unwrap_or_else(|| "native".to_string())
+                    .to_lowercase(),
+                bb_path: config.bb_path,
+                threads: config.threads.filter(|t| *t > 0),
+            };
+            check_backend_config(&config)?;
+            Ok(BBHandle { slot: std::sync::Mutex::new(Some(Backend::new(config)?)) })
+        })();
+
+        match res {
+            Ok(handle) => {
+                let handle: *mut BBHandle = Box::leak(Box::new(handle));
+                LIVE_HANDLES.lock().unwrap_or_else(|e| e.into_inner()).insert(handle as usize);
+                handle
+            }
+            Err(e) => {
+                logging::log(logging::LogLevel::Error, format!("Failed to create backend: {}", e));
+                null_mut()
+            }
+        }
+    })
+}
+
+/// Shuts down a handle's backend like `bb_shutdown` and frees it, once a call
+/// in progress on it has returned. Later calls on the handle fail with
+/// `BB_ERR_INVALID_ARGUMENT`. Null and already freed handles are ignored.
+#[no_mangle]
+pub extern "C" fn bb_backend_free(handle: *mut BBHandle) {
+    ffi_boundary_or((), || {
+        if !LIVE_HANDLES.lock().unwrap_or_else(|e| e.into_inner()).remove(&(handle as usize)) {
+            return;
+        }
+        // Handles are never deallocated, see `BBHandle`.
+        let handle = unsafe { &*handle };
+        let backend = handle.slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(backend) = backend {
+            backend.shutdown();
+        }
+    })
+}
+
+/// The handle behind `handle` if it came from `bb_backend_new` and hasn't
+/// been freed.
+fn live_handle(handle: *const BBHandle) -> Option<&'static BBHandle> {
+    if !LIVE_HANDLES.lock().unwrap_or_else(|e| e.into_inner()).contains(&(handle as usize)) {
+        return None;
+    }
+    // Handles are never deallocated, see `BBHandle`.
+    Some(unsafe { &*handle })
+}
+
+/// Runs an entrypoint against `handle` instead of the global backend.
+fn with_handle(handle: *const BBHandle, f: impl FnOnce() -> BBResult) -> BBResult {
+    if handle.is_null() {
+        return err(FfiError::invalid_argument("Null backend handle"));
+    }
+    match live_handle(handle) {
+        Some(handle) => with_slot(&handle.slot, f),
+        None => err(FfiError::invalid_argument("Unknown or freed backend handle")),
+    }
+}
+
+/// `bb_prove_ultrahonk` on a backend handle.
+#[no_mangle]
+pub extern "C" fn bb_handle_prove_ultrahonk(
+    handle: *const BBHandle,
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        with_handle(handle, || bb_prove_ultrahonk(bytecode_b64_gz, witness_json, settings_json))
+    })
+}
+
+/// `bb_verify_ultrahonk_ex` on a backend handle.
+#[no_mangle]
+pub extern "C" fn bb_handle_verify_ultrahonk(
+    handle: *const BBHandle,
     proof_msgpack_ptr: *const u8,
     proof_msgpack_len: usize,
     vk_ptr: *const u8,
     vk_len: usize,
     settings_json: *const c_char,
-) -> bool {
-    let res: Result<bool, String> = (|| {
-        if proof_msgpack_ptr.is_null() || vk_ptr.is_null() {
-            return Err("null pointer".into());
-        }
-        let proof_msgpack = unsafe { std::slice::from_raw_parts(proof_msgpack_ptr, proof_msgpack_len) };
-        let vk_bytes = unsafe { std::slice::from_raw_parts(vk_ptr, vk_len) }.to_vec();
-        
-        let settings_str = unsafe { cstr_to_string(settings_json) }?;
-        let settings: ProofSystemSettings = serde_json::from_str(&settings_str).map_err(|e| e.to_string())?;
-
-        let prove_resp: CircuitProveResponse = rmp_serde::from_slice(proof_msgpack)
-            .map_err(|e| format!("Failed to deserialize proof response: {}", e))?;
-
-        let verified = match call_bb(Command::CircuitVerify(barretenberg_rs::generated_types::CircuitVerify::new(vk_bytes, prove_resp.public_inputs, prove_resp.proof, settings)))? {
-            barretenberg_rs::generated_types::Response::CircuitVerifyResponse(r) => r,
-            _ => return Err("Unexpected response".to_string()),
-        };
-            
-        Ok(verified.verified)
-    })();
+) -> BBResult {
+    ffi_boundary(|| {
+        with_handle(handle, || {
+            bb_verify_ultrahonk_ex(proof_msgpack_ptr, proof_msgpack_len, vk_ptr, vk_len, settings_json)
+        })
+    })
+}
+
+/// `bb_get_vk_ultrahonk` on a backend handle.
+#[no_mangle]
+pub extern "C" fn bb_handle_get_vk_ultrahonk(
+    handle: *const BBHandle,
+    bytecode_b64_gz: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        with_handle(handle, || bb_get_vk_ultrahonk(bytecode_b64_gz, settings_json))
+    })
+}
+
+/// `bb_last_backend_output` on a backend handle.
+#[no_mangle]
+pub extern "C" fn bb_handle_last_backend_output(handle: *const BBHandle) -> BBResult {
+    ffi_boundary(|| with_handle(handle, bb_last_backend_output))
+}
+
+/// Gzips a proof (or any buffer) and returns it base64 encoded, the same
+/// transport encoding the bytecode arguments use.
+#[no_mangle]
+pub extern "C" fn bb_compress_proof(proof_ptr: *const u8, proof_len: usize) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            use std::io::Write;
+
+            let proof = unsafe { byte_slice(proof_ptr, proof_len) }?;
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(proof).map_err(FfiError::serialization)?;
+            let compressed = encoder.finish().map_err(FfiError::serialization)?;
+            Ok(general_purpose::STANDARD.encode(compressed).into_bytes())
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Reverses `bb_compress_proof`, returning the original bytes. Subject to the
+/// same size limit as decompressed bytecode.
+#[no_mangle]
+pub extern "C" fn bb_decompress_proof(proof_b64_gz: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let proof_str = unsafe { cstr_to_string(proof_b64_gz) }?;
+            decode_b64_gz(&proof_str, "proof")
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Returns the witness bytes the prove entrypoints send to the backend for
+/// `witness_json` (same format as for `bb_prove_ultrahonk`): a msgpack witness
+/// stack with a single entry, prefixed by its format byte. Lets the witness be
+/// prepared separately from proving, e.g. for a remote prover.
+#[no_mangle]
+pub extern "C" fn bb_encode_witness(witness_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        match unsafe { encode_witness_json(witness_json) } {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
+}
 
-    res.unwrap_or(false)
+/// Returns what the `bb` subprocess of the global pipe backend wrote to
+/// stderr during its last call (at most the last 64 KiB), e.g. to attach to
+/// the report of a failed proof. Waits for a call in progress, so the output
+/// is that of a whole call. The output is kept per backend, not per caller,
+/// so this is only meaningful while one caller uses the backend: concurrent
+/// callers should each use their own handle and
+/// `bb_handle_last_backend_output`, or the `backend_output` context of the
+/// error, which is always the failed call's. Empty for the native backend,
+/// whose output goes straight to this process's stderr.
+#[no_mangle]
+pub extern "C" fn bb_last_backend_output() -> BBResult {
+    ffi_boundary(|| {
+        let output = lock_backend().as_ref().map_or_else(String::new, |b| b.output.text());
+        ok(output.into_bytes())
+    })
+}
+
+/// Dry run of `bb_prove_ultrahonk`: decodes the bytecode, parses and
+/// range-checks every witness value and resolves the settings, returning ok
+/// or the first error. Never touches the backend, so it is cheap enough to
+/// reject bad requests before queueing them for proving.
+#[no_mangle]
+pub extern "C" fn bb_prove_validate(
+    bytecode_b64_gz: *const c_char,
+    witness_json: *const c_char,
+    settings_json: *const c_char,
+) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<(), FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            decode_bytecode(&bytecode_str)?;
+            unsafe { encode_witness_json(witness_json) }?;
+            let settings_str = unsafe { settings_arg(settings_json) }?;
+            resolve_settings(&settings_str)?;
+            Ok(())
+        })();
+
+        match res {
+            Ok(()) => ok(vec![]),
+            Err(e) => err(e),
+        }
+    })
+}
+
+/// Returns the number of public inputs of the circuit, read from its VK
+/// (computed or cached), as a little-endian u32. Counts the circuit's own
+/// inputs only, not the ones the backend reserves.
+#[no_mangle]
+pub extern "C" fn bb_public_input_count(bytecode_b64_gz: *const c_char, settings_json: *const c_char) -> BBResult {
+    ffi_boundary(|| {
+        let res: Result<Vec<u8>, FfiError> = (|| {
+            let bytecode_str = unsafe { cstr_to_string(bytecode_b64_gz) }?;
+            let bytecode = decode_bytecode(&bytecode_str)?;
+            let settings = unsafe { parse_settings(settings_json) }?;
+
+            let vk = cached_vk(&bytecode, &settings)?;
+            let count = vk_num_public_inputs(&vk, &settings)?;
+            let count = u32::try_from(count).map_err(|_| FfiError::decode("Invalid public input count in verification key"))?;
+            Ok(count.to_le_bytes().to_vec())
+        })();
+
+        match res {
+            Ok(v) => ok(v),
+            Err(e) => err(e),
+        }
+    })
 }
@@ -0,0 +1,83 @@
+//! Log events forwarded to a host-provided callback, including the stderr of
+//! the `bb` subprocess spawned by the pipe backend, whose output during a call
+//! is also kept by the backend for `bb_last_backend_output`.
+
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+
+/// Log levels passed to the callback, most severe first.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+/// Receives one log line; `msg` is only valid for the duration of the call.
+pub type LogCallback = extern "C" fn(level: i32, msg: *const c_char);
+
+static CALLBACK: Mutex<Option<LogCallback>> = Mutex::new(None);
+
+pub(crate) fn set_callback(callback: Option<LogCallback>) {
+    *CALLBACK.lock().unwrap_or_else(|e| e.into_inner()) = callback;
+}
+
+fn callback() -> Option<LogCallback> {
+    *CALLBACK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn log(level: LogLevel, msg: impl std::fmt::Display) {
+    // Called outside the lock, so the callback may itself change the callback.
+    let Some(callback) = callback() else {
+        return;
+    };
+    let msg = CString::new(msg.to_string().replace('\0', "\\0")).unwrap_or_default();
+    callback(level as i32, msg.as_ptr());
+}
+
+/// Most backend output kept by an `Output`, in bytes.
+const OUTPUT_CAPACITY: usize = 64 * 1024;
+
+/// Output of one backend's `bb` subprocesses since the start of its current
+/// or last call: lines, oldest first, and their total length.
+#[derive(Default)]
+pub(crate) struct Output(Mutex<(VecDeque<String>, usize)>);
+
+impl Output {
+    fn push(&self, line: String) {
+        let mut output = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let (lines, len) = &mut *output;
+        *len += line.len() + 1;
+        lines.push_back(line);
+        while *len > OUTPUT_CAPACITY {
+            match lines.pop_front() {
+                Some(oldest) => *len -= oldest.len() + 1,
+                None => break,
+            }
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut output = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        output.0.clear();
+        output.1 = 0;
+    }
+
+    /// Output since the last `clear()`, up to the last `OUTPUT_CAPACITY`
+    /// bytes, one line per line.
+    pub(crate) fn text(&self) -> String {
+        let output = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        output.0.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
+/// Passes on a line the `bb` subprocess wrote to stderr: records it in
+/// `output` and forwards it to the callback, if any, at `Debug` level.
+pub(crate) fn backend_line(output: &Output, line: String) {
+    log(LogLevel::Debug, format!("bb: {}", line));
+    output.push(line);
+}
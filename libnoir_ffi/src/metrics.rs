@@ -0,0 +1,41 @@
+//! Process-wide counters, snapshotted as JSON by `bb_metrics_json`.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub(crate) static PROOFS_STARTED: AtomicU64 = AtomicU64::new(0);
+pub(crate) static PROOFS_SUCCEEDED: AtomicU64 = AtomicU64::new(0);
+pub(crate) static PROOFS_FAILED: AtomicU64 = AtomicU64::new(0);
+pub(crate) static VKS_COMPUTED: AtomicU64 = AtomicU64::new(0);
+pub(crate) static VERIFICATIONS_RUN: AtomicU64 = AtomicU64::new(0);
+pub(crate) static BACKEND_CALLS: AtomicU64 = AtomicU64::new(0);
+pub(crate) static BACKEND_RESTARTS: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn inc(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Serialize)]
+pub(crate) struct Snapshot {
+    proofs_started: u64,
+    proofs_succeeded: u64,
+    proofs_failed: u64,
+    vks_computed: u64,
+    verifications_run: u64,
+    backend_calls: u64,
+    backend_restarts: u64,
+}
+
+/// Reads every counter. Each read is atomic, the snapshot as a whole is not.
+pub(crate) fn snapshot() -> Snapshot {
+    let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    Snapshot {
+        proofs_started: get(&PROOFS_STARTED),
+        proofs_succeeded: get(&PROOFS_SUCCEEDED),
+        proofs_failed: get(&PROOFS_FAILED),
+        vks_computed: get(&VKS_COMPUTED),
+        verifications_run: get(&VERIFICATIONS_RUN),
+        backend_calls: get(&BACKEND_CALLS),
+        backend_restarts: get(&BACKEND_RESTARTS),
+    }
+}
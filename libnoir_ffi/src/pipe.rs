@@ -0,0 +1,157 @@
+//! Transport to a `bb msgpack run` subprocess spawned by this library rather
+//! than by `barretenberg_rs::backends::PipeBackend`, so the pid used to abort
+//! a hung call is taken from the `Child` itself. Commands and responses are
+//! msgpack buffers framed by a little-endian `u32` length.
+//!
+//! `bb`'s stderr is read on the calling thread while it waits for the
+//! response, and drained once the response is in, so each call's output is
+//! complete when the call returns.
+
+use crate::logging;
+use barretenberg_rs::{Backend, BarretenbergError};
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+
+pub(crate) struct BbProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+    /// `None` once `bb` closed it.
+    stderr: Option<ChildStderr>,
+    /// Start of a stderr line whose end hasn't been read yet.
+    partial_line: Vec<u8>,
+    output: Arc<logging::Output>,
+}
+
+impl BbProcess {
+    /// Starts `bb` with `threads` threads, loading the CRS from `crs_path`
+    /// if given. Both are set in the subprocess's environment only. Its
+    /// stderr lines go to `output`.
+    pub(crate) fn spawn(
+        bb_path: &str,
+        threads: usize,
+        crs_path: Option<&str>,
+        output: Arc<logging::Output>,
+    ) -> std::io::Result<Self> {
+        let mut cmd = Command::new(bb_path);
+        cmd.args(["msgpack", "run"]).env("HARDWARE_CONCURRENCY", threads.to_string());
+        if let Some(crs_path) = crs_path {
+            cmd.env("CRS_PATH", crs_path);
+        }
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout), Some(stderr)) = (child.stdin.take(), child.stdout.take(), child.stderr.take()) else {
+            unreachable!("stdin, stdout and stderr are piped");
+        };
+        set_nonblocking(stdout.as_raw_fd())?;
+        set_nonblocking(stderr.as_raw_fd())?;
+        Ok(BbProcess { child, stdin, stdout, stderr: Some(stderr), partial_line: Vec::new(), output })
+    }
+
+    /// Pid of the subprocess. It stays ours until reaped by `process::wait_exit`,
+    /// so it cannot be reused by an unrelated process before then.
+    pub(crate) fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    fn round_trip(&mut self, input: &[u8]) -> std::io::Result<Vec<u8>> {
+        let len = u32::try_from(input.len()).map_err(|_| std::io::Error::new(ErrorKind::InvalidInput, "command too large"))?;
+        self.stdin.write_all(&len.to_le_bytes())?;
+        self.stdin.write_all(input)?;
+        self.stdin.flush()?;
+
+        let mut response = Vec::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            if response.len() >= 4 {
+                let len = u32::from_le_bytes([response[0], response[1], response[2], response[3]]) as usize;
+                if response.len() >= 4 + len {
+                    response.truncate(4 + len);
+                    return Ok(response.split_off(4));
+                }
+            }
+
+            let stderr_fd = self.stderr.as_ref().map_or(-1, |stderr| stderr.as_raw_fd());
+            let mut fds = [
+                libc::pollfd { fd: self.stdout.as_raw_fd(), events: libc::POLLIN, revents: 0 },
+                // A negative fd is skipped.
+                libc::pollfd { fd: stderr_fd, events: libc::POLLIN, revents: 0 },
+            ];
+            if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() == ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e);
+            }
+            if fds[1].revents != 0 {
+                self.read_stderr();
+            }
+            if fds[0].revents != 0 {
+                match self.stdout.read(&mut buf) {
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => response.extend_from_slice(&buf[..n]),
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+
+    /// Passes on what `bb` has written to stderr so far, without blocking.
+    /// A lost stderr only loses diagnostics, so errors just stop the reading.
+    fn read_stderr(&mut self) {
+        let Some(stderr) = self.stderr.as_mut() else {
+            return;
+        };
+        let mut buf = [0u8; 4096];
+        let closed = loop {
+            match stderr.read(&mut buf) {
+                Ok(0) => break true,
+                Ok(n) => self.partial_line.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => break e.kind() != ErrorKind::WouldBlock,
+            }
+        };
+
+        while let Some(end) = self.partial_line.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial_line.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line[..end]).trim_end_matches('\r').to_string();
+            logging::backend_line(&self.output, line);
+        }
+        if closed {
+            if !self.partial_line.is_empty() {
+                let line = String::from_utf8_lossy(&std::mem::take(&mut self.partial_line)).into_owned();
+                logging::backend_line(&self.output, line);
+            }
+            self.stderr = None;
+        }
+    }
+}
+
+fn set_nonblocking(fd: libc::c_int) -> std::io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+impl Backend for BbProcess {
+    fn call(&mut self, input: &[u8]) -> Result<Vec<u8>, BarretenbergError> {
+        let res = self.round_trip(input);
+        // `bb` writes its diagnostics before the response, or before dying,
+        // so they are in the pipe by now.
+        self.read_stderr();
+        // Worded so `is_broken_pipe` recognizes a subprocess that went away.
+        res.map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => BarretenbergError::Backend("bb pipe: unexpected end of file".to_string()),
+            _ => BarretenbergError::Backend(format!("bb pipe: {}", e)),
+        })
+    }
+}
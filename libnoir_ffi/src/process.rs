@@ -0,0 +1,61 @@
+//! Control of the `bb` subprocess spawned by the pipe backend: pinning it to
+//! CPUs, killing a hung one and reaping it. Pids come from `pipe::BbProcess`.
+
+/// Pins every thread of process `pid` to CPUs `0..cpus`. Threads it starts
+/// later inherit the mask of the thread that starts them. False if any thread
+/// could not be pinned.
+#[cfg(target_os = "linux")]
+pub(crate) fn pin_to_cpus(pid: u32, cpus: usize) -> bool {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for cpu in 0..cpus.clamp(1, libc::CPU_SETSIZE as usize) {
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{}/task", pid)) else {
+        return false;
+    };
+    tasks
+        .flatten()
+        .filter_map(|task| task.file_name().to_str()?.parse::<libc::pid_t>().ok())
+        .fold(true, |pinned, tid| {
+            let res = unsafe { libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+            pinned && res == 0
+        })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn pin_to_cpus(_pid: u32, _cpus: usize) -> bool {
+    false
+}
+
+#[cfg(unix)]
+pub(crate) fn kill(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) == 0 }
+}
+
+// Only the pipe backend has a subprocess, and it is Unix-only.
+#[cfg(not(unix))]
+pub(crate) fn kill(_pid: u32) -> bool {
+    false
+}
+
+/// Waits up to `timeout` for the child `pid` to exit, reaping it. Also true if
+/// it was already reaped elsewhere.
+#[cfg(unix)]
+pub(crate) fn wait_exit(pid: u32, timeout: std::time::Duration) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let res = unsafe { libc::waitpid(pid as libc::pid_t, std::ptr::null_mut(), libc::WNOHANG) };
+        if res != 0 {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn wait_exit(_pid: u32, _timeout: std::time::Duration) -> bool {
+    true
+}
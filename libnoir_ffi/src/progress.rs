@@ -0,0 +1,45 @@
+//! Coarse progress of a proof, reported to a host-provided callback as the
+//! library moves between phases. The bb API exposes no finer-grained
+//! progress, so there is none within a phase.
+
+use std::sync::Mutex;
+
+/// Phases passed to the callback, in the order a proof goes through them.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Computing the verification key; skipped on a VK cache hit.
+    ComputingVk = 0,
+    /// The backend is generating the proof.
+    Proving = 1,
+    /// The proof is done.
+    Done = 2,
+}
+
+impl Phase {
+    /// Rough share of the work done when the phase starts, in percent.
+    fn percent(self) -> f32 {
+        match self {
+            Phase::ComputingVk => 0.0,
+            Phase::Proving => 20.0,
+            Phase::Done => 100.0,
+        }
+    }
+}
+
+/// Receives a phase and the approximate percentage done.
+pub type ProgressCallback = extern "C" fn(phase: i32, pct: f32);
+
+static CALLBACK: Mutex<Option<ProgressCallback>> = Mutex::new(None);
+
+pub(crate) fn set_callback(callback: Option<ProgressCallback>) {
+    *CALLBACK.lock().unwrap_or_else(|e| e.into_inner()) = callback;
+}
+
+pub(crate) fn report(phase: Phase) {
+    // Called outside the lock, like the log callback.
+    let callback = *CALLBACK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(callback) = callback {
+        callback(phase as i32, phase.percent());
+    }
+}
@@ -0,0 +1,68 @@
+//! Optional proving latency measurements, switched on with `bb_enable_timing`.
+//! A prove entrypoint calls `start()`; the VK computation and the prove call
+//! report their durations, and `finish()` publishes the totals for
+//! `bb_last_timing`.
+
+use serde::Serialize;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub(crate) static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static LAST: Mutex<Option<ProveTiming>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub(crate) struct ProveTiming {
+    /// Time spent computing the VK; 0 when it came from the cache.
+    pub(crate) vk_ms: u64,
+    pub(crate) prove_ms: u64,
+    /// From the start of the entrypoint, so it includes decoding the inputs.
+    pub(crate) total_ms: u64,
+}
+
+thread_local! {
+    /// Start of the entrypoint running on this thread and the VK time so far.
+    static CURRENT: Cell<Option<(Instant, u64)>> = const { Cell::new(None) };
+}
+
+pub(crate) fn start() {
+    CURRENT.with(|c| c.set(Some((Instant::now(), 0))));
+}
+
+/// Runs `f`, returning its result and how long it took in milliseconds.
+pub(crate) fn measure<T>(f: impl FnOnce() -> T) -> (T, u64) {
+    let started = Instant::now();
+    let res = f();
+    (res, started.elapsed().as_millis() as u64)
+}
+
+pub(crate) fn add_vk_ms(ms: u64) {
+    CURRENT.with(|c| {
+        if let Some((started, vk_ms)) = c.get() {
+            c.set(Some((started, vk_ms + ms)));
+        }
+    });
+}
+
+/// Records a finished proof. Without a preceding `start()` on this thread the
+/// total covers the prove call only.
+pub(crate) fn finish(prove_ms: u64) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let (vk_ms, total_ms) = match CURRENT.with(|c| c.get()) {
+        Some((started, vk_ms)) => (vk_ms, started.elapsed().as_millis() as u64),
+        None => (0, prove_ms),
+    };
+    *LAST.lock().unwrap_or_else(|e| e.into_inner()) = Some(ProveTiming {
+        vk_ms,
+        prove_ms,
+        total_ms,
+    });
+}
+
+pub(crate) fn last() -> Option<ProveTiming> {
+    *LAST.lock().unwrap_or_else(|e| e.into_inner())
+}
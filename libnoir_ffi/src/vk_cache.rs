@@ -0,0 +1,140 @@
+//! In-memory LRU cache of verification keys, keyed by a hash of the
+//! decompressed bytecode and the serialized proof system settings.
+
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub(crate) type CacheKey = [u8; 32];
+
+pub(crate) fn cache_key(bytecode: &[u8], settings_json: &[u8]) -> CacheKey {
+    let mut hasher = Sha256::new();
+    hasher.update((bytecode.len() as u64).to_le_bytes());
+    hasher.update(bytecode);
+    hasher.update(settings_json);
+    hasher.finalize().into()
+}
+
+/// On-disk form of the cache: the backend that computed the VKs, and the
+/// entries from least to most recently used.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    backend: String,
+    entries: Vec<(ByteBuf, ByteBuf)>,
+}
+
+struct Entry {
+    vk: Vec<u8>,
+    /// `VkCache::clock` value at the last use; the smallest is evicted first.
+    last_used: AtomicU64,
+}
+
+/// Lookups only need `&self`, recording recency atomically, so the cache can
+/// sit behind a `RwLock` and concurrent hits share the read lock.
+pub(crate) struct VkCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Entry>,
+    clock: AtomicU64,
+}
+
+impl VkCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        VkCache {
+            capacity,
+            entries: HashMap::new(),
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<Vec<u8>> {
+        let entry = self.entries.get(key)?;
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+        Some(entry.vk.clone())
+    }
+
+    pub(crate) fn insert(&mut self, key: CacheKey, vk: Vec<u8>) {
+        let last_used = AtomicU64::new(self.tick());
+        self.entries.insert(key, Entry { vk, last_used });
+        while self.entries.len() > self.capacity {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(key, _)| *key);
+            if let Some(lru) = lru {
+                self.entries.remove(&lru);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Keys from least to most recently used.
+    fn keys_by_use(&self) -> Vec<&CacheKey> {
+        let mut keys: Vec<_> = self.entries.keys().collect();
+        keys.sort_by_key(|key| self.entries[*key].last_used.load(Ordering::Relaxed));
+        keys
+    }
+
+    /// Writes the cache to `path`, tagged with `backend`. The file is written
+    /// next to `path` first and then renamed over it.
+    pub(crate) fn save(&self, path: &Path, backend: &str) -> io::Result<()> {
+        let file = CacheFile {
+            backend: backend.to_string(),
+            entries: self
+                .keys_by_use()
+                .into_iter()
+                .map(|key| {
+                    let vk = &self.entries[key].vk;
+                    (ByteBuf::from(key.to_vec()), ByteBuf::from(vk.clone()))
+                })
+                .collect(),
+        };
+        let bytes =
+            rmp_serde::to_vec(&file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Adds the entries saved at `path`, unless they were computed by a
+    /// backend other than `backend`, in which case none are used. Returns the
+    /// number of entries loaded.
+    pub(crate) fn load(&mut self, path: &Path, backend: &str) -> io::Result<usize> {
+        let bytes = std::fs::read(path)?;
+        let file: CacheFile = rmp_serde::from_slice(&bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if file.backend != backend {
+            return Ok(0);
+        }
+
+        // Validate the whole file before touching the cache.
+        let entries = file
+            .entries
+            .into_iter()
+            .map(|(key, vk)| {
+                let key: CacheKey = key.as_slice().try_into().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "cache key is not 32 bytes")
+                })?;
+                Ok((key, vk.into_vec()))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let loaded = entries.len();
+        for (key, vk) in entries {
+            self.insert(key, vk);
+        }
+        Ok(loaded)
+    }
+}